@@ -1,52 +1,350 @@
 use ark_bls12_381::{Bls12_381, Fr, G1Projective, G2Projective, g1::Config as G1Config};
-use ark_ec::AffineRepr;
 use ark_ec::hashing::curve_maps::wb::WBMap;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_ec::{PrimeGroup, pairing::Pairing};
-use ark_ff::{One, Zero};
+use ark_ff::{Field, PrimeField};
 
 use ark_ec::hashing::{HashToCurve, map_to_curve_hasher::MapToCurveBasedHasher};
 use ark_ff::field_hashers::DefaultFieldHasher;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use num_bigint::BigUint;
 use sha2::Sha256;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
 
 use crate::errors::AlgebraError;
 
-pub type Scalar = Fr;
-pub type G1 = G1Projective;
-pub type G2 = G2Projective;
-pub type GT = <Bls12_381 as Pairing>::TargetField;
+#[cfg(feature = "blst")]
+mod blst_backend;
+#[cfg(feature = "blst")]
+pub use blst_backend::BlstBackend;
+
+/// Pairing-friendly curve backend the scheme is instantiated over.
+///
+/// Every protocol function in [`crate::api`] and the types they exchange
+/// take a `C: Curve` parameter defaulting to [`DefaultCurve`], so another
+/// backend plugs in by implementing this trait and naming it, e.g.
+/// `Params::<K, MyCurve>::new()`. Byte and text encodings, the runtime-`K`
+/// `Dyn*` API, [`Signer`](crate::signer::Signer),
+/// [`Verifier`](crate::verifier::Verifier) and the integer-coefficient
+/// program constructors fix their wire format or point checks to
+/// `DefaultCurve` and stay on it, using the aliases below.
+pub trait Curve: Clone + Copy + std::fmt::Debug + PartialEq + Eq + Send + Sync + 'static {
+    type Scalar: PrimeField;
+    type G1: CurveGroup<ScalarField = Self::Scalar>;
+    type G2: CurveGroup<ScalarField = Self::Scalar>;
+    type GT: Field;
+    /// Hash-to-curve hasher into `G1`.
+    type H2G1: Send + Sync;
+
+    fn g1_gen() -> Self::G1 {
+        Self::G1::generator()
+    }
+
+    fn g2_gen() -> Self::G2 {
+        Self::G2::generator()
+    }
+
+    fn pairing(a: &Self::G1, b: &Self::G2) -> Self::GT;
+
+    /// `G2` point with its Miller-loop line coefficients precomputed.
+    type G2Prepared: Clone + std::fmt::Debug + Send + Sync;
+
+    fn prepare_g2(p: &Self::G2) -> Self::G2Prepared;
+
+    /// $\prod_i e(a_i, b_i)$ with a single final exponentiation. `b` is
+    /// borrowed so callers can reuse cached preparations across checks.
+    fn multi_pairing_prepared(a: &[Self::G1], b: &[&Self::G2Prepared]) -> Self::GT;
+
+    fn make_h2g1(dst: &[u8]) -> Result<Self::H2G1, AlgebraError>;
+
+    /// Hashes `msg` into `G1` per RFC 9380. The empty message is valid input:
+    /// the expander accepts it and it maps to a fixed, non-identity subgroup
+    /// point like any other message.
+    fn hash_to_g1(hasher: &Self::H2G1, msg: &[u8]) -> Result<Self::G1, AlgebraError>;
+
+    /// Hashes every message in `msgs` into `G1` with the same hasher.
+    ///
+    /// With the `parallel` feature the messages are hashed on the rayon
+    /// thread pool; otherwise this is a plain sequential map over
+    /// [`Curve::hash_to_g1`].
+    fn hash_to_g1_batch(
+        hasher: &Self::H2G1,
+        msgs: &[&[u8]],
+    ) -> Result<Vec<Self::G1>, AlgebraError> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            msgs.par_iter()
+                .map(|msg| Self::hash_to_g1(hasher, msg))
+                .collect()
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            msgs.iter()
+                .map(|msg| Self::hash_to_g1(hasher, msg))
+                .collect()
+        }
+    }
+}
+
+/// BLS12-381 via arkworks, hashing to `G1` with the RFC 9380 SSWU map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bls12_381Backend;
+
+impl Curve for Bls12_381Backend {
+    type Scalar = Fr;
+    type G1 = G1Projective;
+    type G2 = G2Projective;
+    type GT = <Bls12_381 as Pairing>::TargetField;
+    type H2G1 =
+        MapToCurveBasedHasher<G1Projective, DefaultFieldHasher<Sha256, 128>, WBMap<G1Config>>;
+
+    fn pairing(a: &Self::G1, b: &Self::G2) -> Self::GT {
+        Bls12_381::pairing(a, b).0
+    }
+
+    type G2Prepared = <Bls12_381 as Pairing>::G2Prepared;
+
+    fn prepare_g2(p: &Self::G2) -> Self::G2Prepared {
+        p.into_affine().into()
+    }
+
+    fn multi_pairing_prepared(a: &[Self::G1], b: &[&Self::G2Prepared]) -> Self::GT {
+        let a = Self::G1::normalize_batch(a);
+        // arkworks consumes its prepared inputs, so the clone happens here
+        Bls12_381::multi_pairing(a, b.iter().map(|p| (*p).clone())).0
+    }
+
+    fn make_h2g1(dst: &[u8]) -> Result<Self::H2G1, AlgebraError> {
+        Self::H2G1::new(dst).map_err(|e| AlgebraError::HashToCurve(Box::new(e)))
+    }
+
+    fn hash_to_g1(hasher: &Self::H2G1, msg: &[u8]) -> Result<Self::G1, AlgebraError> {
+        let p = hasher
+            .hash(msg)
+            .map_err(|e| AlgebraError::HashToCurve(Box::new(e)))?;
+        Ok(p.into_group())
+    }
+}
+
+#[cfg(not(feature = "blst"))]
+pub type DefaultCurve = Bls12_381Backend;
+/// With the `blst` feature, pairings and hashing run through `blst`.
+#[cfg(feature = "blst")]
+pub type DefaultCurve = BlstBackend;
+
+/// Scalar field of the scheme: `Fr` of BLS12-381, i.e. integers modulo the
+/// prime group order `r` (see [`scalar_field_modulus`]).
+pub type Scalar = <DefaultCurve as Curve>::Scalar;
+pub type G1 = <DefaultCurve as Curve>::G1;
+pub type G2 = <DefaultCurve as Curve>::G2;
+pub type GT = <DefaultCurve as Curve>::GT;
+pub type G1Affine = <G1 as CurveGroup>::Affine;
+pub type G2Affine = <G2 as CurveGroup>::Affine;
+
+/// Length in bytes of a compressed `G1` point.
+pub const G1_COMPRESSED_LEN: usize = 48;
+/// Length in bytes of a compressed `G2` point.
+pub const G2_COMPRESSED_LEN: usize = 96;
+/// Length in bytes of a canonically encoded [`Scalar`].
+pub const SCALAR_LEN: usize = 32;
 
 pub fn g1_gen() -> G1 {
-    G1::generator()
+    DefaultCurve::g1_gen()
 }
 
 pub fn g2_gen() -> G2 {
-    G2::generator()
+    DefaultCurve::g2_gen()
 }
 
-pub fn gt_one() -> GT {
-    GT::one()
+/// $s \cdot g_2$, i.e. the public key of secret scalar `s`.
+pub fn g2_mul_gen(s: &Scalar) -> G2 {
+    g2_gen() * s
 }
 
-pub fn g1_zero() -> G1 {
-    G1::zero()
+/// Modulus `r` of the scalar field, which is also the prime order of `G1`, `G2`
+/// and `GT`. Coefficients and messages are reduced modulo this value.
+pub fn scalar_field_modulus() -> BigUint {
+    Scalar::MODULUS.into()
 }
 
-pub fn pairing(a: &G1, b: &G2) -> GT {
-    Bls12_381::pairing(a, b).0
+/// Sum of non-negative integers kept both as a field element and as an exact
+/// integer, so callers who mean integer arithmetic learn when the field sum
+/// stops matching it.
+#[derive(Clone, Debug, Default)]
+pub struct CheckedScalarSum {
+    value: Scalar,
+    magnitude: BigUint,
 }
 
-pub type H2G1 =
-    MapToCurveBasedHasher<G1Projective, DefaultFieldHasher<Sha256, 128>, WBMap<G1Config>>;
+impl CheckedScalarSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `v`. Returns [`AlgebraError::Wraparound`] once the integer sum
+    /// reaches the modulus `r`, from then on `value` is the sum reduced mod
+    /// `r`. The term is still added, so the error is a warning only.
+    pub fn add(&mut self, v: impl Into<BigUint>) -> Result<(), AlgebraError> {
+        let v: BigUint = v.into();
+        self.value += Scalar::from(v.clone());
+        self.magnitude += v;
+        if self.wrapped() {
+            return Err(AlgebraError::Wraparound);
+        }
+        Ok(())
+    }
 
-pub fn make_h2g1(dst: &'static [u8]) -> Result<H2G1, AlgebraError> {
-    H2G1::new(dst).map_err(|e| AlgebraError::HashToCurve(Box::new(e)))
+    /// Whether the integer sum has reached the modulus.
+    pub fn wrapped(&self) -> bool {
+        self.magnitude >= scalar_field_modulus()
+    }
+
+    /// The sum in the field.
+    pub const fn value(&self) -> Scalar {
+        self.value
+    }
+
+    /// The exact integer sum.
+    pub fn magnitude(&self) -> &BigUint {
+        &self.magnitude
+    }
+}
+
+/// Linear combination `sum_i s_i * P_i` over `G1`, buffered and evaluated in
+/// one variable-base multi-scalar multiplication on [`Msm::finalize`].
+#[derive(Clone, Debug)]
+pub struct Msm<C: Curve = DefaultCurve> {
+    scalars: Vec<C::Scalar>,
+    points: Vec<C::G1>,
+}
+
+/// Empty combination over any backend; [`Msm::new`] is this on
+/// [`DefaultCurve`].
+impl<C: Curve> Default for Msm<C> {
+    fn default() -> Self {
+        Self {
+            scalars: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+}
+
+impl Msm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C: Curve> Msm<C> {
+    pub fn with_capacity(n: usize) -> Self {
+        Self {
+            scalars: Vec::with_capacity(n),
+            points: Vec::with_capacity(n),
+        }
+    }
+
+    /// Queues the term `scalar * point`.
+    pub fn add(&mut self, scalar: C::Scalar, point: C::G1) {
+        self.scalars.push(scalar);
+        self.points.push(point);
+    }
+
+    /// Number of queued terms.
+    pub fn len(&self) -> usize {
+        self.scalars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scalars.is_empty()
+    }
+
+    /// Evaluates the combination; the identity if no terms were added.
+    pub fn finalize(self) -> C::G1 {
+        let bases = C::G1::normalize_batch(&self.points);
+        C::G1::msm(&bases, &self.scalars).expect("scalars and points have equal length")
+    }
+}
+
+/// Maps a signed integer into the field, sending a negative `v` to
+/// `r - |v|`. Covers the full `i128` range, including `i128::MIN`.
+pub fn scalar_from_i128(v: i128) -> Scalar {
+    let abs = Scalar::from(v.unsigned_abs());
+    if v < 0 { -abs } else { abs }
+}
+
+/// Canonical encoding of a scalar: its integer value in `[0, r)` as
+/// `SCALAR_LEN` little-endian bytes. Every scalar byte conversion in the
+/// crate goes through this and [`scalar_from_bytes`].
+pub fn scalar_to_bytes(s: &Scalar) -> [u8; SCALAR_LEN] {
+    let mut out = [0u8; SCALAR_LEN];
+    s.serialize_compressed(&mut out[..])
+        .expect("scalar encoding is SCALAR_LEN bytes");
+    out
 }
 
-pub fn hash_to_g1_with(hasher: &H2G1, msg: &[u8]) -> Result<G1, AlgebraError> {
-    let p = hasher
-        .hash(msg)
-        .map_err(|e| AlgebraError::HashToCurve(Box::new(e)))?;
-    Ok(p.into_group())
+/// Inverse of [`scalar_to_bytes`]. Rejects input that is not exactly
+/// `SCALAR_LEN` bytes or encodes an integer `>= r`, so each scalar has a
+/// single accepted encoding.
+pub fn scalar_from_bytes(bytes: &[u8]) -> Result<Scalar, AlgebraError> {
+    if bytes.len() != SCALAR_LEN {
+        return Err(AlgebraError::InvalidScalar(format!(
+            "expected {SCALAR_LEN} bytes, got {}",
+            bytes.len()
+        )));
+    }
+    Scalar::deserialize_with_mode(bytes, Compress::Yes, Validate::Yes)
+        .map_err(|e| AlgebraError::InvalidScalar(e.to_string()))
+}
+
+/// Compares two scalars (of any backend) in constant time over their
+/// canonical integer limbs, without short-circuiting on the first differing
+/// limb.
+pub fn scalar_ct_eq<F: PrimeField>(a: &F, b: &F) -> Choice {
+    a.into_bigint().as_ref().ct_eq(b.into_bigint().as_ref())
+}
+
+/// Compares two `GT` elements (of any backend) in constant time over their
+/// canonical encodings. Every verification verdict goes through this, so the
+/// timing of the final comparison does not depend on where the two sides
+/// differ.
+pub fn gt_ct_eq<F: Field>(a: &F, b: &F) -> Choice {
+    let mut ea = Vec::with_capacity(a.compressed_size());
+    let mut eb = Vec::with_capacity(b.compressed_size());
+    a.serialize_compressed(&mut ea)
+        .expect("writing to a Vec cannot fail");
+    b.serialize_compressed(&mut eb)
+        .expect("writing to a Vec cannot fail");
+    ea.ct_eq(&eb)
+}
+
+/// `if cond { a } else { b }` without a data-dependent branch: every byte
+/// of both canonical encodings is read and masked with `subtle`.
+pub fn ct_select(cond: Choice, a: &Scalar, b: &Scalar) -> Scalar {
+    let (ea, eb) = (scalar_to_bytes(a), scalar_to_bytes(b));
+    let bytes: [u8; SCALAR_LEN] =
+        std::array::from_fn(|i| u8::conditional_select(&eb[i], &ea[i], cond));
+    // both inputs are canonical encodings, so the selection is too
+    scalar_from_bytes(&bytes).expect("selected bytes are a canonical scalar")
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Number of pairings evaluated on this thread, for cost assertions.
+    pub(crate) static PAIRING_COUNT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+pub fn pairing(a: &G1, b: &G2) -> GT {
+    curve_pairing::<DefaultCurve>(a, b)
+}
+
+/// [`Curve::pairing`] on any backend. Generic protocol code pairs through
+/// this rather than the trait so test cost assertions still count it.
+pub(crate) fn curve_pairing<C: Curve>(a: &C::G1, b: &C::G2) -> C::GT {
+    #[cfg(test)]
+    PAIRING_COUNT.with(|c| c.set(c.get() + 1));
+    C::pairing(a, b)
 }
 
 // NOTE: This might be a better approach then using map_err
@@ -60,11 +358,11 @@ pub fn hash_to_g1_with(hasher: &H2G1, msg: &[u8]) -> Result<G1, AlgebraError> {
 mod tests {
     use super::*;
     use ark_ec::CurveGroup;
-    use ark_ff::Zero;
+    use ark_ff::{One, Zero};
 
     fn hash_to_g1(dst: &'static [u8], msg: &[u8]) -> Result<G1, AlgebraError> {
-        let h = make_h2g1(dst)?;
-        hash_to_g1_with(&h, msg)
+        let h = DefaultCurve::make_h2g1(dst)?;
+        DefaultCurve::hash_to_g1(&h, msg)
     }
 
     #[test]
@@ -80,6 +378,29 @@ mod tests {
         assert!(a.is_in_correct_subgroup_assuming_on_curve());
     }
 
+    #[test]
+    fn hash_to_g1_empty_message() {
+        use ark_ff::BigInteger;
+
+        // RFC 9380 J.9.1, BLS12381G1_XMD:SHA-256_SSWU_RO_, msg = ""
+        let dst = b"QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+        let p = hash_to_g1(dst, b"").expect("empty message must hash");
+        assert_eq!(p, hash_to_g1(dst, b"").unwrap());
+        assert!(!p.is_zero());
+
+        let a = p.into_affine();
+        assert!(a.is_on_curve());
+        assert!(a.is_in_correct_subgroup_assuming_on_curve());
+        assert_eq!(
+            hex::encode(a.x.into_bigint().to_bytes_be()),
+            "052926add2207b76ca4fa57a8734416c8dc95e24501772c814278700eed6d1e4e8cf62d9c09db0fac349612b759e79a1"
+        );
+        assert_eq!(
+            hex::encode(a.y.into_bigint().to_bytes_be()),
+            "08ba738453bfed09cb546dbb0783dbb3a5f1f566ed67bb6be0e8c67e2e81a4cc68ee29813bb7994998f3eae0c9c6a265"
+        );
+    }
+
     #[test]
     fn hash_to_g1_properties() {
         let dst = b"hejsan";
@@ -94,4 +415,203 @@ mod tests {
         let p3 = hash_to_g1(b"hejsansvejsan", msg).unwrap();
         assert_ne!(p1, p3);
     }
+
+    #[test]
+    fn checked_sum_signals_wraparound() {
+        let r = scalar_field_modulus();
+        let half = &r / 2u32;
+
+        let mut sum = CheckedScalarSum::new();
+        sum.add(half.clone()).unwrap();
+        sum.add(half.clone()).unwrap();
+        assert!(!sum.wrapped());
+        assert_eq!(*sum.magnitude(), &half * 2u32);
+
+        // r is odd, so 2 * (r / 2) + 1 == r wraps to zero
+        assert!(matches!(sum.add(1u32), Err(AlgebraError::Wraparound)));
+        assert!(sum.wrapped());
+        assert!(sum.value().is_zero());
+        assert_eq!(*sum.magnitude(), r);
+
+        let mut small = CheckedScalarSum::new();
+        for v in [3u64, 5, 7] {
+            small.add(v).unwrap();
+        }
+        assert_eq!(small.value(), Scalar::from(15u64));
+    }
+
+    #[test]
+    fn scalar_bytes_are_little_endian() {
+        let mut one = [0u8; SCALAR_LEN];
+        one[0] = 1;
+        assert_eq!(scalar_to_bytes(&Scalar::from(1u64)), one);
+
+        let mut v = [0u8; SCALAR_LEN];
+        v[..2].copy_from_slice(&[0x34, 0x12]);
+        assert_eq!(scalar_to_bytes(&Scalar::from(0x1234u64)), v);
+        assert_eq!(scalar_from_bytes(&v).unwrap(), Scalar::from(0x1234u64));
+    }
+
+    #[test]
+    fn scalar_from_bytes_rejects_bad_input() {
+        assert!(matches!(
+            scalar_from_bytes(&[0xff; SCALAR_LEN]),
+            Err(AlgebraError::InvalidScalar(_))
+        ));
+        assert!(scalar_from_bytes(&[0u8; SCALAR_LEN - 1]).is_err());
+        assert!(scalar_from_bytes(&[0u8; SCALAR_LEN + 1]).is_err());
+
+        let r_minus_one = -Scalar::from(1u64);
+        assert_eq!(
+            scalar_from_bytes(&scalar_to_bytes(&r_minus_one)).unwrap(),
+            r_minus_one
+        );
+    }
+
+    #[test]
+    fn g2_mul_gen_matches_generator_mul() {
+        use ark_std::{UniformRand, test_rng};
+
+        let mut rng = test_rng();
+        for _ in 0..4 {
+            let s = Scalar::rand(&mut rng);
+            assert_eq!(g2_mul_gen(&s), g2_gen() * s);
+        }
+        assert!(g2_mul_gen(&Scalar::from(0u64)).is_zero());
+    }
+
+    #[test]
+    fn multi_pairing_prepared_matches_product() {
+        use ark_std::{UniformRand, test_rng};
+
+        let mut rng = test_rng();
+        let a: Vec<G1> = (0..3).map(|_| G1::rand(&mut rng)).collect();
+        let b: Vec<G2> = (0..3).map(|_| G2::rand(&mut rng)).collect();
+
+        let expected = a
+            .iter()
+            .zip(&b)
+            .fold(GT::one(), |acc, (x, y)| acc * pairing(x, y));
+        let prepared: Vec<<DefaultCurve as Curve>::G2Prepared> =
+            b.iter().map(DefaultCurve::prepare_g2).collect();
+
+        let refs: Vec<&<DefaultCurve as Curve>::G2Prepared> = prepared.iter().collect();
+        assert_eq!(DefaultCurve::multi_pairing_prepared(&a, &refs), expected);
+        assert_eq!(DefaultCurve::multi_pairing_prepared(&[], &[]), GT::one());
+    }
+
+    #[test]
+    fn ct_select_matches_branch() {
+        use ark_std::{UniformRand, test_rng};
+
+        let mut rng = test_rng();
+        for _ in 0..8 {
+            let (a, b) = (Scalar::rand(&mut rng), Scalar::rand(&mut rng));
+            for cond in [false, true] {
+                let naive = if cond { a } else { b };
+                assert_eq!(ct_select(Choice::from(cond as u8), &a, &b), naive);
+            }
+        }
+        let one = Scalar::one();
+        assert_eq!(ct_select(Choice::from(1), &one, &Scalar::zero()), one);
+    }
+
+    #[test]
+    fn gt_ct_eq_matches_eq() {
+        use ark_std::{UniformRand, test_rng};
+
+        let mut rng = test_rng();
+        let x = pairing(&G1::rand(&mut rng), &G2::rand(&mut rng));
+        let y = pairing(&G1::rand(&mut rng), &G2::rand(&mut rng));
+        assert!(bool::from(gt_ct_eq(&x, &x)));
+        assert!(!bool::from(gt_ct_eq(&x, &y)));
+        assert!(bool::from(gt_ct_eq(&GT::one(), &GT::one())));
+        assert!(!bool::from(gt_ct_eq(&x, &GT::one())));
+    }
+
+    #[test]
+    fn msm_matches_variable_base_msm() {
+        use ark_std::{UniformRand, test_rng};
+
+        let mut rng = test_rng();
+        let scalars: Vec<Scalar> = (0..10).map(|_| Scalar::rand(&mut rng)).collect();
+        let points: Vec<G1> = (0..10).map(|_| G1::rand(&mut rng)).collect();
+
+        let mut acc = Msm::new();
+        for (s, p) in scalars.iter().zip(&points) {
+            acc.add(*s, *p);
+        }
+        assert_eq!(acc.len(), 10);
+
+        let bases = G1::normalize_batch(&points);
+        assert_eq!(acc.finalize(), G1::msm(&bases, &scalars).unwrap());
+        assert!(Msm::new().finalize().is_zero());
+    }
+
+    #[test]
+    fn scalar_field_modulus_matches_fr() {
+        let r = scalar_field_modulus();
+        assert_eq!(r, BigUint::from(Fr::MODULUS));
+
+        // BLS12-381 group order
+        let expected = BigUint::parse_bytes(
+            b"73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001",
+            16,
+        )
+        .unwrap();
+        assert_eq!(r, expected);
+
+        // r - 1 is the largest canonical scalar, i.e. -1
+        let r_minus_one = Scalar::from(r - 1u32);
+        assert_eq!(r_minus_one + Scalar::one(), Scalar::zero());
+    }
+
+    #[test]
+    fn scalar_from_i128_signed() {
+        assert_eq!(scalar_from_i128(-1) + Scalar::from(1u64), Scalar::zero());
+        assert_eq!(scalar_from_i128(0), Scalar::zero());
+        assert_eq!(scalar_from_i128(42), Scalar::from(42u64));
+        assert_eq!(scalar_from_i128(-7) + scalar_from_i128(7), Scalar::zero());
+
+        // -1 is r - 1
+        let r_minus_one = Scalar::from(scalar_field_modulus() - 1u32);
+        assert_eq!(scalar_from_i128(-1), r_minus_one);
+
+        // extremes do not overflow
+        assert_eq!(scalar_from_i128(i128::MIN), -Scalar::from(1u128 << 127));
+        assert_eq!(
+            scalar_from_i128(i128::MAX) + Scalar::one(),
+            Scalar::from(1u128 << 127)
+        );
+    }
+
+    #[test]
+    fn scalar_ct_eq_agrees_with_eq() {
+        use ark_std::{UniformRand, test_rng};
+
+        let mut rng = test_rng();
+        for _ in 0..16 {
+            let a = Scalar::rand(&mut rng);
+            let b = Scalar::rand(&mut rng);
+            assert_eq!(bool::from(scalar_ct_eq(&a, &a)), a == a);
+            assert_eq!(bool::from(scalar_ct_eq(&a, &b)), a == b);
+        }
+        assert!(bool::from(scalar_ct_eq(&Scalar::zero(), &Scalar::zero())));
+        assert!(!bool::from(scalar_ct_eq(&Scalar::zero(), &Scalar::one())));
+    }
+
+    #[test]
+    fn hash_to_g1_batch_matches_single() {
+        let h = DefaultCurve::make_h2g1(b"hejsan").unwrap();
+        let msgs: [&[u8]; 4] = [b"hello", b"world", b"", b"hello"];
+
+        let batch = DefaultCurve::hash_to_g1_batch(&h, &msgs).unwrap();
+        let single: Vec<G1> = msgs
+            .iter()
+            .map(|m| DefaultCurve::hash_to_g1(&h, m).unwrap())
+            .collect();
+
+        assert_eq!(batch, single);
+        assert!(DefaultCurve::hash_to_g1_batch(&h, &[]).unwrap().is_empty());
+    }
 }
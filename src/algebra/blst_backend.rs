@@ -0,0 +1,267 @@
+//! BLS12-381 backend running pairings and hash-to-curve through `blst`,
+//! enabled by the `blst` feature. Points, scalars and `GT` stay arkworks
+//! types; they are converted limb by limb at each call, which is exact
+//! because both libraries store `Fp` in Montgomery form with `R = 2^384`
+//! and build the same `Fp2`/`Fp6`/`Fp12` tower.
+
+use ark_bls12_381::{Fq, Fq2, Fq6, Fq12, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInt, One};
+use blst::{
+    blst_fp, blst_fp2, blst_fp6, blst_fp12, blst_hash_to_g1, blst_p1, blst_p1_affine,
+    blst_p1_to_affine, blst_p2_affine,
+};
+
+use super::{Bls12_381Backend, Curve};
+use crate::errors::AlgebraError;
+
+// the limb copies below take `blst_fp` to be six `u64` limbs, as arkworks
+// stores `Fq`; that layout is only guaranteed on 64-bit targets
+#[cfg(not(target_pointer_width = "64"))]
+compile_error!("the `blst` feature is only supported on 64-bit targets");
+
+/// BLS12-381 with `blst` pairings and RFC 9380 SSWU hashing into `G1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlstBackend;
+
+/// Label hasher of [`BlstBackend`]; `blst` takes the DST on every call.
+#[derive(Clone, Debug)]
+pub struct BlstH2G1 {
+    dst: Vec<u8>,
+}
+
+/// Affine `blst` form of a `G2` point, `None` for the identity.
+#[derive(Clone, Debug)]
+pub struct BlstG2Prepared(Option<blst_p2_affine>);
+
+fn fp_to_blst(f: &Fq) -> blst_fp {
+    blst_fp { l: f.0.0 }
+}
+
+fn fp_from_blst(f: &blst_fp) -> Fq {
+    Fq::new_unchecked(BigInt(f.l))
+}
+
+fn fp2_to_blst(f: &Fq2) -> blst_fp2 {
+    blst_fp2 {
+        fp: [fp_to_blst(&f.c0), fp_to_blst(&f.c1)],
+    }
+}
+
+fn fp2_from_blst(f: &blst_fp2) -> Fq2 {
+    Fq2::new(fp_from_blst(&f.fp[0]), fp_from_blst(&f.fp[1]))
+}
+
+fn fp6_from_blst(f: &blst_fp6) -> Fq6 {
+    Fq6::new(
+        fp2_from_blst(&f.fp2[0]),
+        fp2_from_blst(&f.fp2[1]),
+        fp2_from_blst(&f.fp2[2]),
+    )
+}
+
+fn fp12_from_blst(f: &blst_fp12) -> Fq12 {
+    Fq12::new(fp6_from_blst(&f.fp6[0]), fp6_from_blst(&f.fp6[1]))
+}
+
+/// `None` for the identity, which `blst` pairing inputs cannot represent.
+fn g1_to_blst(p: &G1Affine) -> Option<blst_p1_affine> {
+    let (x, y) = p.xy()?;
+    Some(blst_p1_affine {
+        x: fp_to_blst(&x),
+        y: fp_to_blst(&y),
+    })
+}
+
+fn g2_to_blst(p: &G2Affine) -> Option<blst_p2_affine> {
+    let (x, y) = p.xy()?;
+    Some(blst_p2_affine {
+        x: fp2_to_blst(&x),
+        y: fp2_to_blst(&y),
+    })
+}
+
+/// Product of pairings over the pairs with no identity side; an identity
+/// contributes a factor of one.
+fn multi_pairing(pairs: impl Iterator<Item = (blst_p1_affine, blst_p2_affine)>) -> Fq12 {
+    let (ps, qs): (Vec<_>, Vec<_>) = pairs.unzip();
+    if ps.is_empty() {
+        return Fq12::one();
+    }
+    fp12_from_blst(&blst_fp12::miller_loop_n(&qs, &ps).final_exp())
+}
+
+impl Curve for BlstBackend {
+    type Scalar = <Bls12_381Backend as Curve>::Scalar;
+    type G1 = G1Projective;
+    type G2 = G2Projective;
+    type GT = Fq12;
+    type H2G1 = BlstH2G1;
+
+    fn pairing(a: &Self::G1, b: &Self::G2) -> Self::GT {
+        multi_pairing(
+            g1_to_blst(&a.into_affine())
+                .zip(g2_to_blst(&b.into_affine()))
+                .into_iter(),
+        )
+    }
+
+    type G2Prepared = BlstG2Prepared;
+
+    fn prepare_g2(p: &Self::G2) -> Self::G2Prepared {
+        BlstG2Prepared(g2_to_blst(&p.into_affine()))
+    }
+
+    fn multi_pairing_prepared(a: &[Self::G1], b: &[&Self::G2Prepared]) -> Self::GT {
+        let a = Self::G1::normalize_batch(a);
+        multi_pairing(a.iter().zip(b).filter_map(|(p, q)| g1_to_blst(p).zip(q.0)))
+    }
+
+    fn make_h2g1(dst: &[u8]) -> Result<Self::H2G1, AlgebraError> {
+        Ok(BlstH2G1 { dst: dst.to_vec() })
+    }
+
+    #[allow(unsafe_code)]
+    fn hash_to_g1(hasher: &Self::H2G1, msg: &[u8]) -> Result<Self::G1, AlgebraError> {
+        let mut p = blst_p1::default();
+        let mut affine = blst_p1_affine::default();
+        // SAFETY: every pointer comes from a live reference or slice, and
+        // the lengths passed are those of the same slices.
+        unsafe {
+            blst_hash_to_g1(
+                &mut p,
+                msg.as_ptr(),
+                msg.len(),
+                hasher.dst.as_ptr(),
+                hasher.dst.len(),
+                std::ptr::null(),
+                0,
+            );
+            blst_p1_to_affine(&mut affine, &p);
+        }
+        // the hash output is a subgroup point and never the identity
+        let point = G1Affine::new_unchecked(fp_from_blst(&affine.x), fp_from_blst(&affine.y));
+        Ok(point.into_group())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ark_bls12_381::Fr;
+    use ark_ec::PrimeGroup;
+    use ark_ff::Zero;
+    use ark_std::{UniformRand, test_rng};
+
+    use super::*;
+    use crate::{
+        params::Params,
+        protocol::{eval, sign, verify},
+        types::{Id, Label, LabeledProgram, PublicKey, SecretKey, SignAggr, Tag},
+    };
+
+    type Ark = Bls12_381Backend;
+    type Blst = BlstBackend;
+
+    #[test]
+    fn hash_to_g1_matches_arkworks() {
+        let dst = crate::params::DST_H2G1_LABEL;
+        let ark = Ark::make_h2g1(dst).unwrap();
+        let blst = Blst::make_h2g1(dst).unwrap();
+        for msg in [&b""[..], b"abc", &[7u8; 200]] {
+            let p = Blst::hash_to_g1(&blst, msg).unwrap();
+            assert_eq!(p, Ark::hash_to_g1(&ark, msg).unwrap());
+            assert!(p.into_affine().is_in_correct_subgroup_assuming_on_curve());
+        }
+    }
+
+    #[test]
+    fn pairings_match_arkworks() {
+        let mut rng = test_rng();
+        let a: Vec<G1Projective> = (0..3).map(|_| G1Projective::rand(&mut rng)).collect();
+        let b: Vec<G2Projective> = (0..3).map(|_| G2Projective::rand(&mut rng)).collect();
+
+        assert_eq!(Blst::pairing(&a[0], &b[0]), Ark::pairing(&a[0], &b[0]));
+
+        let ark_prepared: Vec<_> = b.iter().map(Ark::prepare_g2).collect();
+        let blst_prepared: Vec<_> = b.iter().map(Blst::prepare_g2).collect();
+        assert_eq!(
+            Blst::multi_pairing_prepared(&a, &blst_prepared.iter().collect::<Vec<_>>()),
+            Ark::multi_pairing_prepared(&a, &ark_prepared.iter().collect::<Vec<_>>())
+        );
+    }
+
+    #[test]
+    fn identity_pairs_are_one() {
+        let g1 = G1Projective::generator();
+        let g2 = G2Projective::generator();
+        assert!(Blst::pairing(&G1Projective::zero(), &g2).is_one());
+        assert!(Blst::pairing(&g1, &G2Projective::zero()).is_one());
+        assert!(Blst::multi_pairing_prepared(&[], &[]).is_one());
+
+        let prepared = [
+            Blst::prepare_g2(&G2Projective::zero()),
+            Blst::prepare_g2(&g2),
+        ];
+        assert_eq!(
+            Blst::multi_pairing_prepared(&[g1, g1], &[&prepared[0], &prepared[1]]),
+            Ark::pairing(&g1, &g2)
+        );
+    }
+
+    /// Signs `msgs` under `labels` with the secret scalars `xs`, evaluates
+    /// `coeffs` over the shares and verifies the aggregate against both the
+    /// true output and a wrong one, all under the backend `C`.
+    fn sign_eval_verify<C: Curve<Scalar = Fr>>(
+        xs: &[Fr],
+        labels: &[Label<8>],
+        msgs: &[Fr],
+        coeffs: &[Fr],
+    ) -> (SignAggr<8, C>, bool, bool) {
+        let pp = Params::<8, C>::new();
+        let sks: HashMap<_, _> = xs
+            .iter()
+            .zip(labels)
+            .map(|(x, l)| (l.id(), SecretKey::<8, C>::new(l.id(), *x)))
+            .collect();
+        let pks: HashMap<_, _> = sks
+            .values()
+            .map(|sk| (sk.id(), PublicKey::new(sk.id(), C::g2_gen() * sk.value())))
+            .collect();
+
+        let shares = labels
+            .iter()
+            .zip(msgs)
+            .map(|(l, m)| sign(&pp, &sks[&l.id()], *l, *m).unwrap())
+            .collect();
+        let program = LabeledProgram::new(coeffs.to_vec(), labels.to_vec()).unwrap();
+        let aggr = eval(&pp, &program, shares).unwrap();
+
+        let output: Fr = coeffs.iter().zip(msgs).map(|(f, m)| *f * m).sum();
+        let accepts = verify(&pp, &program, &pks, output, &aggr).unwrap();
+        let rejects = !verify(&pp, &program, &pks, output + Fr::from(1u64), &aggr).unwrap();
+        (aggr, accepts, rejects)
+    }
+
+    #[test]
+    fn sign_eval_verify_matches_arkworks() {
+        let mut rng = test_rng();
+        let xs: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let labels: Vec<Label<8>> = (0..3u8)
+            .map(|i| Label::new(Id([i + 1; 8]), Tag([i; 8])))
+            .collect();
+        let msgs: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let coeffs = [Fr::from(2u64), Fr::from(3u64), Fr::from(5u64)];
+
+        let (ark, ark_accepts, ark_rejects) = sign_eval_verify::<Ark>(&xs, &labels, &msgs, &coeffs);
+        let (blst, blst_accepts, blst_rejects) =
+            sign_eval_verify::<Blst>(&xs, &labels, &msgs, &coeffs);
+
+        assert_eq!(blst.gamma(), ark.gamma());
+        assert_eq!(blst.ord_ids(), ark.ord_ids());
+        assert_eq!(blst.mus(), ark.mus());
+        assert!(ark_accepts && ark_rejects);
+        assert_eq!((blst_accepts, blst_rejects), (ark_accepts, ark_rejects));
+    }
+}
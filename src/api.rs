@@ -1 +1,24 @@
-pub use crate::protocol::{keygen, sign, eval, verify};
+//! The MKLHS protocol: key generation, signing, evaluation and
+//! verification. Every function here except [`keygen_dyn`] and
+//! [`verify_dyn`] is generic over the curve backend `C` (see [`Curve`]);
+//! those two dispatch on runtime `K` over [`DefaultCurve`] encodings.
+
+pub use crate::algebra::{
+    Bls12_381Backend, CheckedScalarSum, Curve, DefaultCurve, Msm, ct_select, g2_mul_gen,
+    scalar_field_modulus, scalar_from_i128,
+};
+pub use crate::protocol::{
+    EvalOptions, audit_shares, batch_challenge_scalars, batch_verify, batch_verify_fs,
+    batch_verify_report, batch_verify_with_budget, combine, combine_strict, diagnose_failure, eval,
+    eval_by_label, eval_checked, eval_self_contained, eval_sparse, eval_with_breakdown,
+    eval_with_commitment, eval_with_options, expected_gamma, group_shares, keygen, keygen_dyn,
+    keygen_many, pairing_cost, sign, sign_in_domain, sign_vector, verify, verify_dyn,
+    verify_in_domain, verify_key_list, verify_prepared, verify_prepared_keys, verify_result,
+    verify_self_contained, verify_share, verify_terms, verify_with_offset,
+};
+
+#[cfg(feature = "blst")]
+pub use crate::algebra::BlstBackend;
+
+#[cfg(feature = "debug-trace")]
+pub use crate::protocol::eval_trace;
@@ -0,0 +1,252 @@
+//! Everything a verifier needs besides the program and the aggregate.
+
+use std::collections::HashMap;
+
+use crate::{
+    algebra::Scalar,
+    errors::ProtocolError,
+    params::Params,
+    protocol::verify,
+    types::{Id, LabeledProgram, PublicKey, SignAggr},
+};
+
+/// Public keys of the signers together with a fingerprint of the `Params`
+/// they are meant to be used with, so the two cannot drift apart.
+#[derive(Clone, Debug)]
+pub struct VerificationBundle<const K: usize> {
+    dst_fingerprint: [u8; 32],
+    pks: HashMap<Id<K>, PublicKey<K>>,
+}
+
+impl<const K: usize> VerificationBundle<K> {
+    pub fn new(pp: &Params<K>, pks: HashMap<Id<K>, PublicKey<K>>) -> Self {
+        Self {
+            dst_fingerprint: pp.dst_fingerprint(),
+            pks,
+        }
+    }
+
+    /// Builds a bundle from a list of keys in any order. A key repeated
+    /// verbatim is kept once; two different keys for one id are rejected.
+    pub fn from_keys(
+        pp: &Params<K>,
+        pks: impl IntoIterator<Item = PublicKey<K>>,
+    ) -> Result<Self, ProtocolError> {
+        let mut bundle = Self::new(pp, HashMap::new());
+        for pk in pks {
+            bundle.insert(pk)?;
+        }
+        Ok(bundle)
+    }
+
+    /// Adds `pk`, erroring if the bundle already holds a different key for
+    /// its id. Re-inserting the same key is a no-op.
+    pub fn insert(&mut self, pk: PublicKey<K>) -> Result<(), ProtocolError> {
+        match self.pks.get(&pk.id()) {
+            Some(existing) if *existing != pk => Err(ProtocolError::InvalidInput(
+                "conflicting public keys for one id".to_string(),
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.pks.insert(pk.id(), pk);
+                Ok(())
+            }
+        }
+    }
+
+    pub const fn dst_fingerprint(&self) -> &[u8; 32] {
+        &self.dst_fingerprint
+    }
+
+    pub fn pks(&self) -> &HashMap<Id<K>, PublicKey<K>> {
+        &self.pks
+    }
+
+    /// Runs [`verify`] with the bundled keys, failing if `pp` is not the
+    /// configuration the bundle was built for.
+    pub fn verify(
+        &self,
+        pp: &Params<K>,
+        program: &LabeledProgram<K>,
+        msg: Scalar,
+        sign_aggr: &SignAggr<K>,
+    ) -> Result<bool, ProtocolError> {
+        if pp.dst_fingerprint() != self.dst_fingerprint {
+            return Err(ProtocolError::InvalidInput(
+                "params do not match bundle DST fingerprint".to_string(),
+            ));
+        }
+        verify(pp, program, &self.pks, msg, sign_aggr)
+    }
+
+    /// Encodes the bundle as `fingerprint || n (u32 LE) || pk_1 || ... || pk_n`
+    /// with keys sorted by id, so equal bundles encode identically.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut pks: Vec<&PublicKey<K>> = self.pks.values().collect();
+        pks.sort_by_key(|pk| pk.id());
+
+        let mut out = Vec::with_capacity(32 + 4 + pks.len() * PublicKey::<K>::SERIALIZED_LEN);
+        out.extend_from_slice(&self.dst_fingerprint);
+        out.extend_from_slice(&(pks.len() as u32).to_le_bytes());
+        for pk in pks {
+            out.extend_from_slice(&pk.to_bytes());
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        if bytes.len() < 32 + 4 {
+            return Err(ProtocolError::InvalidInput(
+                "verification bundle too short".to_string(),
+            ));
+        }
+        let (fp, rest) = bytes.split_at(32);
+        let (n, rest) = rest.split_at(4);
+        let n = u32::from_le_bytes(n.try_into().expect("split at 4")) as usize;
+
+        let pk_len = PublicKey::<K>::SERIALIZED_LEN;
+        if n.checked_mul(pk_len) != Some(rest.len()) {
+            return Err(ProtocolError::InvalidInput(
+                "verification bundle length does not match key count".to_string(),
+            ));
+        }
+
+        let mut pks = HashMap::with_capacity(n);
+        for chunk in rest.chunks_exact(pk_len) {
+            let pk = PublicKey::from_bytes(chunk)?;
+            if pks.insert(pk.id(), pk).is_some() {
+                return Err(ProtocolError::InvalidInput(
+                    "duplicate id in verification bundle".to_string(),
+                ));
+            }
+        }
+
+        Ok(Self {
+            dst_fingerprint: fp.try_into().expect("split at 32"),
+            pks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        protocol::{eval, keygen, sign},
+        types::{Label, Tag},
+    };
+    use ark_std::{UniformRand, test_rng};
+
+    const K: usize = 8;
+
+    fn setup() -> (
+        Params<K>,
+        VerificationBundle<K>,
+        LabeledProgram<K>,
+        Scalar,
+        SignAggr<K>,
+    ) {
+        let pp = Params::<K>::new();
+        let mut rng = test_rng();
+
+        let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+        let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+
+        let lab_a = Label::new(sk_a.id(), Tag([1u8; K]));
+        let lab_b = Label::new(sk_b.id(), Tag([2u8; K]));
+        let (m_a, m_b) = (Scalar::rand(&mut rng), Scalar::rand(&mut rng));
+
+        let shares = vec![
+            sign(&pp, &sk_a, lab_a, m_a).unwrap(),
+            sign(&pp, &sk_b, lab_b, m_b).unwrap(),
+        ];
+        let program =
+            LabeledProgram::new(vec![Scalar::from(3), Scalar::from(1)], vec![lab_a, lab_b])
+                .unwrap();
+        let aggr = eval(&pp, &program, shares).unwrap();
+
+        let pks = HashMap::from([(pk_a.id(), pk_a), (pk_b.id(), pk_b)]);
+        let bundle = VerificationBundle::new(&pp, pks);
+
+        (pp, bundle, program, Scalar::from(3) * m_a + m_b, aggr)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (_pp, bundle, _, _, _) = setup();
+
+        let bytes = bundle.to_bytes();
+        let back = VerificationBundle::<K>::from_bytes(&bytes).unwrap();
+
+        assert_eq!(back.dst_fingerprint(), bundle.dst_fingerprint());
+        assert_eq!(back.pks().len(), bundle.pks().len());
+        for (id, pk) in bundle.pks() {
+            assert_eq!(back.pks()[id].value(), pk.value());
+        }
+        assert_eq!(back.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn conflicting_keys_are_rejected() {
+        let (pp, bundle, _, _, _) = setup();
+        let mut keys: Vec<PublicKey<K>> = bundle.pks().values().cloned().collect();
+        keys.sort_by_key(|pk| pk.id());
+
+        let forward = VerificationBundle::from_keys(&pp, keys.clone()).unwrap();
+        let mut reversed = keys.clone();
+        reversed.reverse();
+        reversed.push(keys[0].clone());
+        let backward = VerificationBundle::from_keys(&pp, reversed).unwrap();
+        assert_eq!(backward.pks().len(), 2);
+        assert_eq!(forward.to_bytes(), backward.to_bytes());
+        assert_eq!(forward.to_bytes(), bundle.to_bytes());
+
+        let impostor = PublicKey::new(keys[0].id(), *keys[1].value());
+        let mut b = forward.clone();
+        assert!(b.insert(impostor.clone()).is_err());
+        assert_eq!(b.pks()[&keys[0].id()], keys[0]);
+        keys.push(impostor);
+        assert!(VerificationBundle::from_keys(&pp, keys).is_err());
+    }
+
+    #[test]
+    fn malformed_bytes_are_rejected() {
+        let (_pp, bundle, _, _, _) = setup();
+        let bytes = bundle.to_bytes();
+
+        assert!(VerificationBundle::<K>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(VerificationBundle::<K>::from_bytes(&bytes[..20]).is_err());
+
+        // claim one more key than is present
+        let mut wrong_count = bytes.clone();
+        wrong_count[32] += 1;
+        assert!(VerificationBundle::<K>::from_bytes(&wrong_count).is_err());
+    }
+
+    #[test]
+    fn verify_through_bundle() {
+        let (pp, bundle, program, msg, aggr) = setup();
+
+        assert!(bundle.verify(&pp, &program, msg, &aggr).unwrap());
+        assert!(
+            !bundle
+                .verify(&pp, &program, msg + Scalar::from(1), &aggr)
+                .unwrap()
+        );
+
+        let restored = VerificationBundle::<K>::from_bytes(&bundle.to_bytes()).unwrap();
+        assert!(restored.verify(&pp, &program, msg, &aggr).unwrap());
+    }
+
+    #[test]
+    fn mismatched_params_error() {
+        let (pp, bundle, program, msg, aggr) = setup();
+
+        let mut bytes = bundle.to_bytes();
+        bytes[0] ^= 0xff;
+        let other = VerificationBundle::<K>::from_bytes(&bytes).unwrap();
+
+        assert!(other.verify(&pp, &program, msg, &aggr).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+//! Merkle commitment over the labels an aggregate covers, for proving that a
+//! single label is included without disclosing the others.
+
+use sha2::{Digest, Sha256};
+
+use crate::{params::DST_LABEL_MERKLE, types::Label};
+
+/// One level of an inclusion path: the sibling hash and which side it is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MerkleStep {
+    pub sibling: [u8; 32],
+    pub sibling_on_left: bool,
+}
+
+/// Path from a label's leaf to the root. Levels where the node had no
+/// sibling (it was promoted unchanged) contribute no step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabelInclusionProof {
+    steps: Vec<MerkleStep>,
+}
+
+impl LabelInclusionProof {
+    pub fn steps(&self) -> &[MerkleStep] {
+        &self.steps
+    }
+}
+
+fn leaf_hash(label_bytes: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(DST_LABEL_MERKLE)
+        .chain_update([0x00])
+        .chain_update(label_bytes)
+        .finalize()
+        .into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([0x01])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// All tree levels, leaves first. Leaves are the labels sorted by their byte
+/// encoding; an odd node at the end of a level is promoted unchanged.
+fn levels<const K: usize>(labels: &[Label<K>]) -> Vec<Vec<[u8; 32]>> {
+    let mut sorted: Vec<Vec<u8>> = labels.iter().map(Label::to_bytes).collect();
+    sorted.sort();
+    let leaves: Vec<[u8; 32]> = sorted.iter().map(|b| leaf_hash(b)).collect();
+
+    let mut levels = vec![leaves];
+    while levels.last().is_some_and(|l| l.len() > 1) {
+        let level = levels.last().expect("checked above");
+        let next = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [l, r] => node_hash(l, r),
+                [single] => *single,
+                _ => unreachable!("chunks(2) yields one or two nodes"),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Merkle root over the sorted label encodings. The root of an empty label
+/// set is the hash of the DST alone.
+pub fn label_merkle_root<const K: usize>(labels: &[Label<K>]) -> [u8; 32] {
+    match levels(labels).last().and_then(|l| l.first()) {
+        Some(root) => *root,
+        None => Sha256::digest(DST_LABEL_MERKLE).into(),
+    }
+}
+
+/// Inclusion path for `label` in the tree over `labels`, or `None` if it is
+/// not among them.
+pub fn label_inclusion_proof<const K: usize>(
+    labels: &[Label<K>],
+    label: &Label<K>,
+) -> Option<LabelInclusionProof> {
+    let levels = levels(labels);
+    let leaf = leaf_hash(&label.to_bytes());
+    let mut pos = levels.first()?.iter().position(|h| *h == leaf)?;
+
+    let mut steps = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        let sib = pos ^ 1;
+        if let Some(sibling) = level.get(sib) {
+            steps.push(MerkleStep {
+                sibling: *sibling,
+                sibling_on_left: sib < pos,
+            });
+        }
+        pos /= 2;
+    }
+    Some(LabelInclusionProof { steps })
+}
+
+/// Checks that `proof` leads from `label` to `root`.
+pub fn verify_label_inclusion<const K: usize>(
+    root: &[u8; 32],
+    label: &Label<K>,
+    proof: &LabelInclusionProof,
+) -> bool {
+    let computed = proof
+        .steps
+        .iter()
+        .fold(leaf_hash(&label.to_bytes()), |acc, step| {
+            if step.sibling_on_left {
+                node_hash(&step.sibling, &acc)
+            } else {
+                node_hash(&acc, &step.sibling)
+            }
+        });
+    computed == *root
+}
@@ -0,0 +1,164 @@
+//! Pluggable mappings from application bytes to the message `Scalar` that
+//! gets signed.
+
+use ark_ff::PrimeField;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    algebra::{SCALAR_LEN, Scalar, scalar_from_bytes, scalar_from_i128},
+    errors::ProtocolError,
+    params::DST_MESSAGE_HASH,
+};
+
+/// Maps raw application data to a message scalar. Homomorphic evaluation
+/// acts on the scalars, so only encoders that are linear in the data (such
+/// as [`BigEndianInteger`] and [`FixedPoint`]) give meaningful outputs.
+pub trait MessageEncoder {
+    fn encode(&self, raw: &[u8]) -> Result<Scalar, ProtocolError>;
+}
+
+/// Reads `raw` as an unsigned big-endian integer, rejecting values `>= r`.
+/// Leading zero bytes are ignored and empty input encodes zero.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BigEndianInteger;
+
+impl MessageEncoder for BigEndianInteger {
+    fn encode(&self, raw: &[u8]) -> Result<Scalar, ProtocolError> {
+        let start = raw.iter().position(|b| *b != 0).unwrap_or(raw.len());
+        let digits = &raw[start..];
+        if digits.len() > SCALAR_LEN {
+            return Err(ProtocolError::InvalidInput(
+                "integer exceeds the scalar field".to_string(),
+            ));
+        }
+        let mut le = [0u8; SCALAR_LEN];
+        for (dst, src) in le.iter_mut().zip(digits.iter().rev()) {
+            *dst = *src;
+        }
+        scalar_from_bytes(&le).map_err(|_| {
+            ProtocolError::InvalidInput("integer exceeds the scalar field".to_string())
+        })
+    }
+}
+
+/// Hashes `raw` to a scalar: 64 bytes of SHA-256 in counter mode under
+/// [`DST_MESSAGE_HASH`], reduced mod r. Deterministic, but not linear, so
+/// evaluated outputs are only useful as commitments to the inputs.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HashToScalar;
+
+impl MessageEncoder for HashToScalar {
+    fn encode(&self, raw: &[u8]) -> Result<Scalar, ProtocolError> {
+        let mut wide = [0u8; 64];
+        for (i, chunk) in wide.chunks_mut(32).enumerate() {
+            let block = Sha256::new()
+                .chain_update(DST_MESSAGE_HASH)
+                .chain_update(raw)
+                .chain_update([i as u8])
+                .finalize();
+            chunk.copy_from_slice(&block);
+        }
+        Ok(Scalar::from_le_bytes_mod_order(&wide))
+    }
+}
+
+/// Reads `raw` as an ASCII decimal such as `-12.5` and encodes it scaled by
+/// `10^decimals`, negatives mapping to `r - |v|`. Input with more fractional
+/// digits than `decimals`, or whose scaled value does not fit an `i128`, is
+/// rejected rather than rounded.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedPoint {
+    decimals: u32,
+}
+
+impl FixedPoint {
+    pub const fn new(decimals: u32) -> Self {
+        Self { decimals }
+    }
+
+    pub const fn decimals(&self) -> u32 {
+        self.decimals
+    }
+}
+
+impl MessageEncoder for FixedPoint {
+    fn encode(&self, raw: &[u8]) -> Result<Scalar, ProtocolError> {
+        let invalid = |what: &str| ProtocolError::InvalidInput(format!("fixed-point: {what}"));
+
+        let (negative, body) = match raw.split_first() {
+            Some((b'-', rest)) => (true, rest),
+            _ => (false, raw),
+        };
+        let (int_part, frac_part) = match body.iter().position(|b| *b == b'.') {
+            Some(dot) => (&body[..dot], &body[dot + 1..]),
+            None => (body, &[][..]),
+        };
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(invalid("no digits"));
+        }
+        if frac_part.len() > self.decimals as usize {
+            return Err(invalid("too many fractional digits"));
+        }
+
+        let mut value: i128 = 0;
+        let pad = self.decimals as usize - frac_part.len();
+        for b in int_part
+            .iter()
+            .chain(frac_part)
+            .copied()
+            .chain(std::iter::repeat_n(b'0', pad))
+        {
+            if !b.is_ascii_digit() {
+                return Err(invalid("not a decimal number"));
+            }
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add(i128::from(b - b'0')))
+                .ok_or_else(|| invalid("value out of range"))?;
+        }
+        Ok(scalar_from_i128(if negative { -value } else { value }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_round_trips_small_values() {
+        for v in [0u64, 1, 255, 256, 1 << 40, u64::MAX] {
+            assert_eq!(
+                BigEndianInteger.encode(&v.to_be_bytes()).unwrap(),
+                Scalar::from(v)
+            );
+        }
+        assert_eq!(BigEndianInteger.encode(&[]).unwrap(), Scalar::from(0u64));
+        assert_eq!(
+            BigEndianInteger.encode(&[0, 0, 0, 7]).unwrap(),
+            Scalar::from(7u64)
+        );
+        assert!(BigEndianInteger.encode(&[0xFF; SCALAR_LEN]).is_err());
+        assert!(BigEndianInteger.encode(&[1; SCALAR_LEN + 1]).is_err());
+    }
+
+    #[test]
+    fn hash_is_deterministic() {
+        let a = HashToScalar.encode(b"reading-42").unwrap();
+        assert_eq!(a, HashToScalar.encode(b"reading-42").unwrap());
+        assert_ne!(a, HashToScalar.encode(b"reading-43").unwrap());
+        assert_ne!(a, BigEndianInteger.encode(b"reading-42").unwrap());
+    }
+
+    #[test]
+    fn fixed_point_scales() {
+        let fp = FixedPoint::new(2);
+        assert_eq!(fp.encode(b"12.5").unwrap(), Scalar::from(1250u64));
+        assert_eq!(fp.encode(b"3").unwrap(), Scalar::from(300u64));
+        assert_eq!(fp.encode(b".07").unwrap(), Scalar::from(7u64));
+        assert_eq!(fp.encode(b"-0.25").unwrap(), -Scalar::from(25u64));
+        assert!(fp.encode(b"1.234").is_err());
+        assert!(fp.encode(b"1e3").is_err());
+        assert!(fp.encode(b"-").is_err());
+        assert!(FixedPoint::new(30).encode(b"1000000000").is_err());
+    }
+}
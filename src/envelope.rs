@@ -0,0 +1,159 @@
+//! Self-describing JSON envelope for aggregates, enabled by the `serde`
+//! feature. Binary fields are lowercase hex.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algebra::{G1_COMPRESSED_LEN, SCALAR_LEN, scalar_to_bytes},
+    errors::ProtocolError,
+    params::Params,
+    types::SignAggr,
+};
+
+/// One signer of an [`AggregateEnvelope`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeSigner {
+    pub id: String,
+    pub mu: String,
+}
+
+/// [`SignAggr`] with the ciphersuite (the label DST, hex like the other
+/// binary fields since a DST need not be UTF-8) and `K` it was made under,
+/// both checked on decode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateEnvelope {
+    pub ciphersuite: String,
+    pub k: usize,
+    pub gamma: String,
+    pub signers: Vec<EnvelopeSigner>,
+    /// Seconds since the Unix epoch; informational only.
+    pub created_at: u64,
+}
+
+impl AggregateEnvelope {
+    /// Wraps `aggr`, stamping it with the current time.
+    pub fn from_aggr<const K: usize>(pp: &Params<K>, aggr: &SignAggr<K>) -> Self {
+        let bytes = aggr.to_bytes();
+        Self {
+            ciphersuite: hex::encode(pp.dst_h2g1_label()),
+            k: K,
+            gamma: hex::encode(&bytes[..G1_COMPRESSED_LEN]),
+            signers: aggr
+                .iter()
+                .map(|(id, mu)| EnvelopeSigner {
+                    id: hex::encode(id.0),
+                    mu: hex::encode(scalar_to_bytes(mu)),
+                })
+                .collect(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        }
+    }
+
+    /// Recovers the aggregate, rejecting an envelope made under another
+    /// ciphersuite or `K`, or with malformed fields.
+    pub fn into_aggr<const K: usize>(&self, pp: &Params<K>) -> Result<SignAggr<K>, ProtocolError> {
+        if hex::decode(&self.ciphersuite).ok().as_deref() != Some(pp.dst_h2g1_label()) {
+            return Err(ProtocolError::InvalidInput(
+                "envelope ciphersuite does not match params".to_string(),
+            ));
+        }
+        if self.k != K {
+            return Err(ProtocolError::InvalidInput(format!(
+                "envelope K = {} does not match K = {K}",
+                self.k
+            )));
+        }
+
+        let field = |name: &str, s: &str, len: usize| {
+            let b = hex::decode(s)
+                .map_err(|e| ProtocolError::InvalidInput(format!("invalid {name} hex: {e}")))?;
+            if b.len() != len {
+                return Err(ProtocolError::InvalidInput(format!(
+                    "{name} must be {len} bytes, got {}",
+                    b.len()
+                )));
+            }
+            Ok(b)
+        };
+
+        // reassemble the binary encoding so decoding checks stay in one place
+        let mut bytes = field("gamma", &self.gamma, G1_COMPRESSED_LEN)?;
+        let n = u32::try_from(self.signers.len())
+            .map_err(|_| ProtocolError::InvalidInput("too many signers".to_string()))?;
+        bytes.extend_from_slice(&n.to_le_bytes());
+        for signer in &self.signers {
+            bytes.extend_from_slice(&field("id", &signer.id, K)?);
+            bytes.extend_from_slice(&field("mu", &signer.mu, SCALAR_LEN)?);
+        }
+        SignAggr::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{algebra::Scalar, types::Id};
+    use ark_ec::PrimeGroup;
+
+    fn aggr() -> SignAggr<8> {
+        SignAggr::new(
+            crate::algebra::G1::generator() * Scalar::from(7),
+            vec![Id([1u8; 8]), Id([2u8; 8])],
+            vec![Scalar::from(3), Scalar::from(4)],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let pp = Params::<8>::new();
+        let a = aggr();
+
+        let env = AggregateEnvelope::from_aggr(&pp, &a);
+        assert_eq!(env.k, 8);
+        assert_eq!(env.signers.len(), 2);
+
+        let json = serde_json::to_string(&env).unwrap();
+        let back: AggregateEnvelope = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, env);
+        assert_eq!(back.into_aggr(&pp).unwrap(), a);
+    }
+
+    #[test]
+    fn mismatches_are_rejected() {
+        let pp = Params::<8>::new();
+        let env = AggregateEnvelope::from_aggr(&pp, &aggr());
+
+        let other = pp.with_new_dst(b"MKLHS-TEST:OTHER-SUITE").unwrap();
+        assert!(env.into_aggr(&other).is_err());
+        assert!(env.into_aggr(&Params::<16>::new()).is_err());
+
+        let mut bad = env.clone();
+        bad.signers[0].id.pop();
+        assert!(bad.into_aggr(&pp).is_err());
+
+        let mut dup = env.clone();
+        dup.signers[1] = dup.signers[0].clone();
+        assert!(dup.into_aggr(&pp).is_err());
+    }
+
+    #[test]
+    fn non_utf8_dst_round_trips() {
+        let pp = Params::<8>::new()
+            .with_new_dst(b"MKLHS-TEST:\xff\xfe-SUITE")
+            .unwrap();
+        let env = AggregateEnvelope::from_aggr(&pp, &aggr());
+        assert_eq!(env.into_aggr(&pp).unwrap(), aggr());
+
+        // distinct invalid sequences must not collapse to the same suite
+        let other = Params::<8>::new()
+            .with_new_dst(b"MKLHS-TEST:\xfe\xff-SUITE")
+            .unwrap();
+        assert!(env.into_aggr(&other).is_err());
+    }
+}
@@ -4,7 +4,13 @@ use thiserror::Error;
 
 pub enum AlgebraError {
     #[error("hash-to-curve error")]
-    HashToCurve(#[source] Box<dyn std::error::Error>),
+    HashToCurve(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("invalid scalar encoding: {0}")]
+    InvalidScalar(String),
+
+    #[error("integer sum exceeds the scalar field modulus and wrapped around")]
+    Wraparound,
 }
 
 #[derive(Debug, Error)]
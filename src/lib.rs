@@ -3,15 +3,30 @@
 //! Reference: <https://eprint.iacr.org/2019/830>
 //! Research artefact. Not audited. Do not use in production.
 
-#![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "blst"), forbid(unsafe_code))]
+// the `blst` backend needs one FFI call for hash-to-curve
+#![cfg_attr(feature = "blst", deny(unsafe_code))]
 #![warn(clippy::all)]
 // #![warn(missing_docs)]
 
 mod algebra;
 
 pub mod api;
+pub mod bundle;
+pub mod commitment;
+pub mod encoding;
+#[cfg(feature = "serde")]
+pub mod envelope;
 pub mod errors;
 pub mod params;
+pub mod signer;
 pub mod types;
+pub mod verifier;
 
 pub(crate) mod protocol;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+
+#[cfg(test)]
+mod test_vectors;
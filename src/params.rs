@@ -1,32 +1,249 @@
 //! Public parameters for MKLHS.
 
-use crate::algebra::{H2G1, make_h2g1};
+use std::{borrow::Cow, collections::HashMap};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    algebra::{Curve, DefaultCurve},
+    errors::ProtocolError,
+    protocol::{eval, sign, verify},
+    types::{Id, Label, LabeledProgram, PublicKey, SecretKey, Tag},
+};
 
 /// Fixed DST used for hashing labels `ell` into `G1`.
 pub const DST_H2G1_LABEL: &[u8] = b"MKLHS-AP-2019-830:ELL->G1:BLS12-381:V01";
 
-pub struct Params<const K: usize> {
+/// Fixed DST used when deriving tags from a domain and input.
+pub const DST_TAG_DERIVE: &[u8] = b"MKLHS-AP-2019-830:TAG-DERIVE:SHA256:V01";
+
+/// Fixed DST used when fingerprinting a labeled program.
+pub const DST_PROGRAM_FINGERPRINT: &[u8] = b"MKLHS-AP-2019-830:PROGRAM-FP:SHA256:V01";
+
+/// Fixed DST for the Fiat–Shamir transcript of batch verification.
+pub const DST_BATCH_FS: &[u8] = b"MKLHS-AP-2019-830:BATCH-FS:SHA256:V01";
+
+/// Fixed DST for the leaves of the label Merkle commitment.
+pub const DST_LABEL_MERKLE: &[u8] = b"MKLHS-AP-2019-830:LABEL-MERKLE:SHA256:V01";
+
+/// Fixed DST used when deriving labels from human-readable strings.
+pub const DST_LABEL_STR: &[u8] = b"MKLHS-AP-2019-830:LABEL-STR:SHA256:V01";
+
+/// Fixed DST for public key registration commitments.
+pub const DST_KEY_REGISTRATION: &[u8] = b"MKLHS-AP-2019-830:KEY-REGISTRATION:SHA256:V01";
+
+/// Fixed DST used by the hash-to-scalar message encoder.
+pub const DST_MESSAGE_HASH: &[u8] = b"MKLHS-AP-2019-830:MSG->SCALAR:SHA256:V01";
+
+/// Fixed DST for the Fiat–Shamir transcript of share auditing.
+pub const DST_AUDIT_FS: &[u8] = b"MKLHS-AP-2019-830:AUDIT-FS:SHA256:V01";
+
+/// Public parameters over the curve backend `C`; see [`Curve`] for plugging
+/// in a backend other than [`DefaultCurve`].
+pub struct Params<const K: usize, C: Curve = DefaultCurve> {
     /// Hash-to-curve domain separation tag (DST) for H(ell) in G1.
-    dst_h2g1_label: &'static [u8],
+    dst_h2g1_label: Cow<'static, [u8]>,
     /// Stored hasher to reduce separate hasher instantiations.
-    h2g1_label: H2G1,
+    h2g1_label: C::H2G1,
+    /// Largest program `eval` and `verify` accept; `None` is unlimited.
+    max_program_terms: Option<usize>,
+    /// The `G2` generator, prepared once for multi-pairing checks.
+    g2_gen_prepared: C::G2Prepared,
 }
 
-impl<const K: usize> Params<K> {
-    /// `K` is the fixed byte length of ID and Tag space.
+impl<const K: usize, C: Curve> Params<K, C> {
+    /// `K` is the fixed byte length of ID and Tag space. There is no upper
+    /// bound: `K` only sizes the id and tag arrays and their encodings, so a
+    /// large `K` costs bytes, not correctness (`K = 64` is tested). Only
+    /// [`DynAggr`](crate::types::DynAggr) is limited, to `K` of 16 and 32.
+    ///
+    /// `K` must be nonzero; `K = 0` is rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// let _ = mklhs::params::Params::<0>::new();
+    /// ```
     pub fn new() -> Self {
-        let h2g1_label = make_h2g1(DST_H2G1_LABEL).expect("invalid DTS");
+        const { assert!(K > 0, "Params: K must be nonzero") };
+
+        let h2g1_label = C::make_h2g1(DST_H2G1_LABEL).expect("invalid DTS");
         Self {
-            dst_h2g1_label: DST_H2G1_LABEL,
+            dst_h2g1_label: Cow::Borrowed(DST_H2G1_LABEL),
             h2g1_label,
+            max_program_terms: None,
+            g2_gen_prepared: C::prepare_g2(&C::g2_gen()),
+        }
+    }
+
+    /// [`Params::new`] followed by a sign/eval/verify round on fixed inputs,
+    /// failing if the pairing machinery of the curve backend is miswired.
+    pub fn new_with_selftest() -> Result<Self, ProtocolError> {
+        let pp = Self::new();
+        pp.selftest()?;
+        Ok(pp)
+    }
+
+    fn selftest(&self) -> Result<(), ProtocolError> {
+        let id = Id([1u8; K]);
+        let sk = SecretKey::<K, C>::new(id, C::Scalar::from(7u64));
+        let pk = PublicKey::new(id, C::g2_gen() * sk.value());
+        let labels = vec![Label::new(id, Tag([2u8; K])), Label::new(id, Tag([3u8; K]))];
+        let shares = vec![
+            sign(self, &sk, labels[0], C::Scalar::from(5u64))?,
+            sign(self, &sk, labels[1], C::Scalar::from(11u64))?,
+        ];
+        let program =
+            LabeledProgram::new(vec![C::Scalar::from(3u64), C::Scalar::from(2u64)], labels)?;
+        let aggr = eval(self, &program, shares)?;
+
+        let pks = HashMap::from([(id, pk)]);
+        if !verify(self, &program, &pks, C::Scalar::from(37u64), &aggr)?
+            || verify(self, &program, &pks, C::Scalar::from(38u64), &aggr)?
+        {
+            return Err(ProtocolError::InvalidInput(
+                "params self-test failed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Copy of these params hashing labels under `dst` instead, with its own
+    /// hasher; `self` is untouched, so old and new can coexist while a
+    /// deployment migrates ciphersuite versions. Shares signed under one DST
+    /// do not verify under the other.
+    pub fn with_new_dst(&self, dst: &[u8]) -> Result<Params<K, C>, ProtocolError> {
+        if dst.is_empty() {
+            return Err(ProtocolError::InvalidInput(
+                "dst must be nonempty".to_string(),
+            ));
+        }
+        Ok(Self {
+            dst_h2g1_label: Cow::Owned(dst.to_vec()),
+            h2g1_label: C::make_h2g1(dst)?,
+            max_program_terms: self.max_program_terms,
+            g2_gen_prepared: self.g2_gen_prepared.clone(),
+        })
+    }
+
+    /// Caps the number of program terms `eval` and `verify` will process,
+    /// for services that accept untrusted programs.
+    pub fn with_max_program_terms(mut self, max: usize) -> Self {
+        self.max_program_terms = Some(max);
+        self
+    }
+
+    pub const fn max_program_terms(&self) -> Option<usize> {
+        self.max_program_terms
+    }
+
+    /// Errors with `"program too large"` if `n` terms exceed the cap.
+    pub(crate) fn check_program_size(&self, n: usize) -> Result<(), ProtocolError> {
+        match self.max_program_terms {
+            Some(max) if n > max => {
+                Err(ProtocolError::InvalidInput("program too large".to_string()))
+            }
+            _ => Ok(()),
         }
     }
 
-    pub const fn dst_h2g1_label(&self) -> &'static [u8] {
-        self.dst_h2g1_label
+    pub fn dst_h2g1_label(&self) -> &[u8] {
+        &self.dst_h2g1_label
     }
 
-    pub fn h2g1_label(&self) -> &H2G1 {
+    pub fn h2g1_label(&self) -> &C::H2G1 {
         &self.h2g1_label
     }
+
+    pub(crate) fn g2_gen_prepared(&self) -> &C::G2Prepared {
+        &self.g2_gen_prepared
+    }
+
+    /// The point `H(ell)` that `sign` and `verify` use for `label`, for
+    /// callers reimplementing verification.
+    pub fn hash_label(&self, label: &Label<K>) -> Result<C::G1, ProtocolError> {
+        Ok(C::hash_to_g1(&self.h2g1_label, &label.to_bytes())?)
+    }
+
+    /// SHA-256 of the label DST, identifying the hashing configuration
+    /// without carrying the DST itself.
+    pub fn dst_fingerprint(&self) -> [u8; 32] {
+        Sha256::digest(&self.dst_h2g1_label).into()
+    }
+}
+
+impl Params<16> {
+    /// Params for 16-byte ids and tags.
+    pub fn k16() -> Self {
+        Self::new()
+    }
+}
+
+impl Params<32> {
+    /// Params for 32-byte ids and tags.
+    ///
+    /// ```
+    /// let pp = mklhs::params::Params::k32();
+    /// assert_eq!(pp.dst_fingerprint(), mklhs::params::Params::<32>::new().dst_fingerprint());
+    /// ```
+    pub fn k32() -> Self {
+        Self::new()
+    }
+}
+
+impl<const K: usize, C: Curve> Default for Params<K, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algebra::{Scalar, g1_gen};
+
+    #[test]
+    fn named_constructors_match_new() {
+        let a = Params::k32();
+        let b = Params::<32>::new();
+        assert_eq!(a.dst_h2g1_label(), b.dst_h2g1_label());
+        assert_eq!(a.dst_fingerprint(), b.dst_fingerprint());
+
+        let c = Params::k16();
+        assert_eq!(c.dst_fingerprint(), Params::<16>::new().dst_fingerprint());
+    }
+
+    #[test]
+    fn selftest_passes_by_default() {
+        let pp = Params::<8>::new_with_selftest().unwrap();
+        assert_eq!(pp.dst_fingerprint(), Params::<8>::new().dst_fingerprint());
+        assert!(Params::<32>::new_with_selftest().is_ok());
+    }
+
+    #[test]
+    fn hash_label_matches_sign() {
+        let pp = Params::<8>::new();
+        let id = Id([4u8; 8]);
+        let sk = SecretKey::new(id, Scalar::from(9u64));
+        let label = Label::new(id, Tag([5u8; 8]));
+        let msg = Scalar::from(13u64);
+
+        let share = sign(&pp, &sk, label, msg).unwrap();
+        let h = pp.hash_label(&label).unwrap();
+        assert_eq!(*share.gamma(), (h + g1_gen() * msg) * sk.value());
+        assert_ne!(h, pp.hash_label(&Label::new(id, Tag([6u8; 8]))).unwrap());
+    }
+
+    #[test]
+    fn program_size_cap() {
+        let pp = Params::<8>::new();
+        assert_eq!(pp.max_program_terms(), None);
+        assert!(pp.check_program_size(usize::MAX).is_ok());
+
+        let pp = pp.with_max_program_terms(3);
+        assert!(pp.check_program_size(3).is_ok());
+        assert!(matches!(
+            pp.check_program_size(4),
+            Err(ProtocolError::InvalidInput(msg)) if msg == "program too large"
+        ));
+    }
 }
@@ -1,58 +1,315 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    algebra::{G1, GT, Scalar, g1_gen, g1_zero, g2_gen, gt_one, hash_to_g1_with, pairing},
+    algebra::{Curve, Msm, Scalar, curve_pairing, gt_ct_eq, scalar_ct_eq},
+    commitment::label_merkle_root,
     errors::ProtocolError,
-    params::Params,
-    types::{Id, Label, LabeledProgram, PublicKey, SecretKey, SignAggr, SignShare},
+    params::{DST_AUDIT_FS, DST_BATCH_FS, Params},
+    types::{
+        BatchItem, DynAggr, DynVerifier, Id, Label, LabeledProgram, PreparedProgram,
+        PreparedPublicKey, PublicKey, SecretKey, SignAggr, SignShare, SparseProgram,
+    },
 };
 
+use ark_ff::{One, PrimeField};
+use ark_serialize::CanonicalSerialize;
 use ark_std::{UniformRand, Zero, rand::RngCore};
+use sha2::{Digest, Sha256};
 
-pub fn keygen<const K: usize, R: RngCore>(
-    _pp: &Params<K>,
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+pub fn keygen<const K: usize, C: Curve, R: RngCore>(
+    _pp: &Params<K, C>,
     rng: &mut R,
-) -> Result<(SecretKey<K>, PublicKey<K>), ProtocolError> {
+) -> Result<(SecretKey<K, C>, PublicKey<K, C>), ProtocolError> {
     let mut id_bytes = [0u8; K];
     rng.try_fill_bytes(&mut id_bytes)
         .map_err(|e| ProtocolError::Rng(e.to_string()))?;
     let id = Id(id_bytes);
 
-    let mut x = Scalar::rand(rng);
+    Ok(keypair_for_id(id, rng))
+}
+
+/// [`keygen`] over a trait-object RNG, e.g. a hardware source behind
+/// `Box<dyn RngCore>`. It is instantiated once for all RNG types, keeping
+/// FFI wrappers from monomorphizing per caller.
+pub fn keygen_dyn<const K: usize>(
+    pp: &Params<K>,
+    mut rng: &mut dyn RngCore,
+) -> Result<(SecretKey<K>, PublicKey<K>), ProtocolError> {
+    keygen(pp, &mut rng)
+}
+
+/// Public keys indexed by signer id, as [`verify`] takes them.
+type PublicKeys<const K: usize, C> = HashMap<Id<K>, PublicKey<K, C>>;
+
+/// Weighted `(label_i, f_i * mu_i)` contributions of [`eval_with_breakdown`].
+type Breakdown<const K: usize, C> = Vec<(Label<K>, <C as Curve>::Scalar)>;
+
+/// Generates `n` keypairs with distinct ids, returning the public keys
+/// already indexed by id for [`verify`]. An id collision is redrawn; after
+/// `KEYGEN_MANY_ATTEMPTS` collisions in a row (only plausible for tiny `K`)
+/// this gives up with [`ProtocolError::Rng`].
+pub fn keygen_many<const K: usize, C: Curve, R: RngCore>(
+    pp: &Params<K, C>,
+    n: usize,
+    rng: &mut R,
+) -> Result<(Vec<SecretKey<K, C>>, PublicKeys<K, C>), ProtocolError> {
+    const KEYGEN_MANY_ATTEMPTS: usize = 64;
+
+    let mut sks = Vec::with_capacity(n);
+    let mut pks = HashMap::with_capacity(n);
+    while sks.len() < n {
+        let mut fresh = None;
+        for _ in 0..KEYGEN_MANY_ATTEMPTS {
+            let (sk, pk) = keygen(pp, rng)?;
+            if !pks.contains_key(&pk.id()) {
+                fresh = Some((sk, pk));
+                break;
+            }
+        }
+        let (sk, pk) =
+            fresh.ok_or_else(|| ProtocolError::Rng("could not draw a fresh id".to_string()))?;
+        pks.insert(pk.id(), pk);
+        sks.push(sk);
+    }
+    Ok((sks, pks))
+}
+
+/// Samples a fresh nonzero secret scalar for `id` and derives its public key.
+pub(crate) fn keypair_for_id<const K: usize, C: Curve, R: RngCore>(
+    id: Id<K>,
+    rng: &mut R,
+) -> (SecretKey<K, C>, PublicKey<K, C>) {
+    let mut x = C::Scalar::rand(rng);
     while x.is_zero() {
-        x = Scalar::rand(rng);
+        x = C::Scalar::rand(rng);
     }
     let sk = SecretKey::new(id, x);
 
-    let g2x = g2_gen() * x;
+    let pk = PublicKey::new(id, C::g2_gen() * x);
 
-    let pk = PublicKey::new(id, g2x);
+    (sk, pk)
+}
 
-    Ok((sk, pk))
+/// Signs `msg` under `label` with `sk`.
+///
+/// Signing is deterministic: it draws no randomness, so identical
+/// `(pp, sk, label, msg)` always give byte-identical shares. Any future
+/// randomized variant (e.g. blinding) must be a separate entry point so this
+/// path stays reproducible.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+pub fn sign<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    sk: &SecretKey<K, C>,
+    label: Label<K>,
+    msg: C::Scalar,
+) -> Result<SignShare<K, C>, ProtocolError> {
+    sign_in_domain(pp, sk, &[], label, msg)
 }
 
-pub fn sign<const K: usize>(
-    pp: &Params<K>,
-    sk: &SecretKey<K>,
+/// Signs a vector record `msgs` under one compound `label`, returning one
+/// share per component; component `i` is signed under
+/// [`Label::component`]`(i)`. Programs over the record reference those
+/// component labels.
+pub fn sign_vector<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    sk: &SecretKey<K, C>,
     label: Label<K>,
-    msg: Scalar,
-) -> Result<SignShare<K>, ProtocolError> {
-    let label_bytes = label.to_bytes();
-    let h = hash_to_g1_with(pp.h2g1_label(), &label_bytes)?;
+    msgs: &[C::Scalar],
+) -> Result<Vec<SignShare<K, C>>, ProtocolError> {
+    msgs.iter()
+        .enumerate()
+        .map(|(i, m)| sign(pp, sk, label.component(i), *m))
+        .collect()
+}
+
+/// [`sign`] with labels namespaced by `dataset`: the hashed label input is
+/// `dataset || label.to_bytes()`, so datasets sharing one [`Params`] get
+/// independent label spaces without new DSTs. The label encoding has fixed
+/// length, so the prefix is unambiguous; an empty `dataset` is plain
+/// [`sign`]. Shares verify only with [`verify_in_domain`] under the same
+/// `dataset`.
+pub fn sign_in_domain<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    sk: &SecretKey<K, C>,
+    dataset: &[u8],
+    label: Label<K>,
+    msg: C::Scalar,
+) -> Result<SignShare<K, C>, ProtocolError> {
+    // a share under a foreign label can never verify; fail here instead
+    if !label.belongs_to(&sk.id()) {
+        return Err(ProtocolError::InvalidInput(
+            "label id does not match signer id".to_string(),
+        ));
+    }
 
-    let gamma = (h + g1_gen() * msg) * (*sk.value());
+    let h = C::hash_to_g1(pp.h2g1_label(), &domain_label_bytes(dataset, &label))?;
+
+    let gamma = (h + C::g1_gen() * msg) * (*sk.value());
 
     Ok(SignShare::new(sk.id(), gamma, msg))
 }
 
+/// Checks a single share against its label and the signer's public key:
+/// $e(\gamma, g_2) = e(H(\ell) + \mu g_1, \mathrm{pk})$.
+///
+/// Returns `Ok(false)` if the share, label and key do not all carry the
+/// same id.
+pub fn verify_share<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    label: &Label<K>,
+    pk: &PublicKey<K, C>,
+    share: &SignShare<K, C>,
+) -> Result<bool, ProtocolError> {
+    if share.id() != label.id() || pk.id() != label.id() {
+        return Ok(false);
+    }
+
+    let h = pp.hash_label(label)?;
+    let lhs = curve_pairing::<C>(share.gamma(), &C::g2_gen());
+    let rhs = curve_pairing::<C>(&(h + C::g1_gen() * share.mu()), pk.value());
+    Ok(gt_ct_eq(&lhs, &rhs).into())
+}
+
+/// Checks every `(label, share)` pair as [`verify_share`] would and reports
+/// `(index, valid)` for each, in input order.
+///
+/// Phase one checks all shares at once: the per-share equations are
+/// combined with Fiat–Shamir weights into a single multi-pairing. Only if
+/// that fails does phase two bisect the set, re-running the combined check
+/// on halves until each failure is pinned to one index. A share whose id
+/// does not match its label, or whose signer has no key in `pks`, is
+/// reported invalid without entering the pairing checks. Errors only if a
+/// label cannot be hashed.
+pub fn audit_shares<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    terms: &[(Label<K>, SignShare<K, C>)],
+) -> Result<Vec<(usize, bool)>, ProtocolError> {
+    let mut valid = vec![false; terms.len()];
+
+    let candidates: Vec<usize> = (0..terms.len())
+        .filter(|&i| {
+            let (label, share) = &terms[i];
+            share.id() == label.id() && pks.contains_key(&label.id())
+        })
+        .collect();
+
+    let label_bytes: Vec<Vec<u8>> = candidates.iter().map(|&i| terms[i].0.to_bytes()).collect();
+    let msgs: Vec<&[u8]> = label_bytes.iter().map(Vec::as_slice).collect();
+    let hs = C::hash_to_g1_batch(pp.h2g1_label(), &msgs)?;
+    let weights = audit_weights(pp, pks, terms);
+    let prepared: HashMap<Id<K>, _> = candidates
+        .iter()
+        .map(|&i| {
+            let id = terms[i].0.id();
+            (id, C::prepare_g2(pks[&id].value()))
+        })
+        .collect();
+
+    // weighted share k: (r * gamma, r * (H(l) + mu * g1), id)
+    let points: Vec<(C::G1, C::G1, Id<K>)> = candidates
+        .iter()
+        .zip(hs)
+        .map(|(&i, h)| {
+            let (label, share) = &terms[i];
+            let r = weights[i];
+            (
+                *share.gamma() * r,
+                (h + C::g1_gen() * share.mu()) * r,
+                label.id(),
+            )
+        })
+        .collect();
+
+    // e(sum r*gamma, g2) * prod_j e(-sum_{k of j} r*(H + mu*g1), pk_j) == 1
+    let holds = |subset: &[usize]| {
+        let mut per_signer: HashMap<Id<K>, C::G1> = HashMap::new();
+        let mut gamma = C::G1::zero();
+        for &k in subset {
+            let (g, p, id) = &points[k];
+            gamma += g;
+            *per_signer.entry(*id).or_insert_with(C::G1::zero) -= p;
+        }
+        let (mut g1s, mut g2s): (Vec<C::G1>, Vec<_>) = per_signer
+            .into_iter()
+            .map(|(id, p)| (p, &prepared[&id]))
+            .unzip();
+        g1s.push(gamma);
+        g2s.push(pp.g2_gen_prepared());
+        bool::from(gt_ct_eq(
+            &C::multi_pairing_prepared(&g1s, &g2s),
+            &C::GT::one(),
+        ))
+    };
+
+    let mut stack = vec![(0..points.len()).collect::<Vec<usize>>()];
+    while let Some(subset) = stack.pop() {
+        if subset.is_empty() {
+            continue;
+        }
+        if holds(&subset) {
+            for k in subset {
+                valid[candidates[k]] = true;
+            }
+        } else if subset.len() > 1 {
+            let (lo, hi) = subset.split_at(subset.len() / 2);
+            stack.push(hi.to_vec());
+            stack.push(lo.to_vec());
+        }
+    }
+
+    Ok(valid.into_iter().enumerate().collect())
+}
+
+/// Fiat–Shamir weights for [`audit_shares`], one per term, binding the
+/// params, every label, share and (if present) signer key.
+fn audit_weights<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    terms: &[(Label<K>, SignShare<K, C>)],
+) -> Vec<C::Scalar> {
+    let mut transcript = Sha256::new()
+        .chain_update(DST_AUDIT_FS)
+        .chain_update(pp.dst_fingerprint())
+        .chain_update((terms.len() as u64).to_le_bytes());
+    let mut buf = Vec::new();
+    for (label, share) in terms {
+        buf.clear();
+        buf.extend_from_slice(&label.to_bytes());
+        buf.extend_from_slice(&share.id().0);
+        put_compressed(&mut buf, share.gamma());
+        put_compressed(&mut buf, share.mu());
+        if let Some(pk) = pks.get(&label.id()) {
+            buf.extend_from_slice(&pk.id().0);
+            put_compressed(&mut buf, pk.value());
+        }
+        transcript.update(&buf);
+    }
+    expand_weights(&transcript.finalize(), terms.len())
+}
+
 fn organize<const K: usize>(labels: &[Label<K>]) -> (Vec<Id<K>>, Vec<Vec<usize>>) {
+    group_by_id(labels.iter().map(Label::id))
+}
+
+/// Buckets `shares` by signer id: first-appearance order of ids, and for each
+/// id the indices of its shares. Same grouping [`eval`] applies to labels.
+pub fn group_shares<const K: usize, C: Curve>(
+    shares: &[SignShare<K, C>],
+) -> (Vec<Id<K>>, Vec<Vec<usize>>) {
+    group_by_id(shares.iter().map(SignShare::id))
+}
+
+fn group_by_id<const K: usize>(
+    ids: impl ExactSizeIterator<Item = Id<K>>,
+) -> (Vec<Id<K>>, Vec<Vec<usize>>) {
     let mut ord_ids: Vec<Id<K>> = Vec::new();
     let mut groups: Vec<Vec<usize>> = Vec::new();
-    let mut id_to_idx: HashMap<Id<K>, usize> = HashMap::with_capacity(labels.len());
+    let mut id_to_idx: HashMap<Id<K>, usize> = HashMap::with_capacity(ids.len());
 
     // O(n) pass to build all
-    for (i, lab) in labels.iter().enumerate() {
-        let id = lab.id();
+    for (i, id) in ids.enumerate() {
         let j = *id_to_idx.entry(id).or_insert_with(|| {
             let j = ord_ids.len();
             ord_ids.push(id);
@@ -65,11 +322,37 @@ fn organize<const K: usize>(labels: &[Label<K>]) -> (Vec<Id<K>>, Vec<Vec<usize>>
     (ord_ids, groups)
 }
 
-pub fn eval<const K: usize>(
-    _pp: &Params<K>,
-    program: &LabeledProgram<K>,
-    sign_shares: Vec<SignShare<K>>,
-) -> Result<SignAggr<K>, ProtocolError> {
+/// Tuning knobs for [`eval_with_options`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EvalOptions {
+    /// Omit signers whose aggregate `mu` is zero and whose partial gamma
+    /// is the identity. Such a signer contributes nothing to either
+    /// equation, and [`verify`] accepts its labels without it as long as
+    /// they cancel, so dropping it only shrinks the aggregate.
+    pub drop_zero_signers: bool,
+}
+
+pub fn eval<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_shares: Vec<SignShare<K, C>>,
+) -> Result<SignAggr<K, C>, ProtocolError> {
+    eval_with_options(pp, program, sign_shares, EvalOptions::default())
+}
+
+/// [`eval`] with explicit [`EvalOptions`].
+pub fn eval_with_options<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_shares: Vec<SignShare<K, C>>,
+    options: EvalOptions,
+) -> Result<SignAggr<K, C>, ProtocolError> {
+    pp.check_program_size(program.n())?;
+
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!("eval", terms = program.n(), signers = tracing::field::Empty)
+        .entered();
+
     let coeffs = program.coeffs();
     let labels = program.labels();
 
@@ -79,554 +362,3770 @@ pub fn eval<const K: usize>(
         ));
     }
 
-    // TODO: research MSM (multi-scalar multiplication) to be used here instead
-    // for performance gain (`ark_ec::msm` or `ark_ec::VariableBaseMSM`).
-    // in particular do criterion benchmark test to see difference also
-    let gamma: G1 = coeffs
-        .iter()
-        .zip(sign_shares.iter())
-        .fold(g1_zero(), |acc, (f, share)| acc + *share.gamma() * f);
+    let mut gamma = Msm::<C>::default();
+    for (f, share) in coeffs.iter().zip(&sign_shares) {
+        gamma.add(*f, *share.gamma());
+    }
+    let gamma = gamma.finalize();
 
-    let (ord_ids, groups) = organize(labels);
+    let (mut ord_ids, groups) = organize(labels);
 
-    let mus: Vec<Scalar> = groups
+    let mut mus: Vec<C::Scalar> = groups
         .iter()
         .map(|idxs| idxs.iter().map(|&i| coeffs[i] * sign_shares[i].mu()).sum())
         .collect();
 
+    if options.drop_zero_signers {
+        let keep: Vec<bool> = groups
+            .iter()
+            .zip(&mus)
+            .map(|(idxs, mu)| {
+                let mut partial = Msm::<C>::default();
+                for &i in idxs {
+                    partial.add(coeffs[i], *sign_shares[i].gamma());
+                }
+                !mu.is_zero() || !partial.finalize().is_zero()
+            })
+            .collect();
+        let mut it = keep.iter();
+        ord_ids.retain(|_| *it.next().unwrap());
+        let mut it = keep.iter();
+        mus.retain(|_| *it.next().unwrap());
+    }
+
+    #[cfg(feature = "tracing")]
+    span.record("signers", ord_ids.len());
+
     SignAggr::new(gamma, ord_ids, mus)
 }
 
-pub fn verify<const K: usize>(
-    pp: &Params<K>,
-    program: &LabeledProgram<K>,
-    pks: &HashMap<Id<K>, PublicKey<K>>,
-    msg: Scalar,
-    sign_aggr: &SignAggr<K>,
-) -> Result<bool, ProtocolError> {
-    // ver1: check $\sum_j \mu_j = m$
-    let mu_sum: Scalar = sign_aggr.mus().iter().sum();
-    if mu_sum != msg {
-        return Ok(false);
-    }
+/// [`eval`] that also commits to the program's labels: the aggregate carries
+/// their Merkle root (see [`crate::commitment`]), so a single label can later
+/// be shown to be covered without revealing the rest.
+pub fn eval_with_commitment<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_shares: Vec<SignShare<K, C>>,
+) -> Result<SignAggr<K, C>, ProtocolError> {
+    let mut aggr = eval(pp, program, sign_shares)?;
+    aggr.set_label_root(label_merkle_root(program.labels()));
+    Ok(aggr)
+}
 
-    // create id to index table
-    let ord_ids = sign_aggr.ord_ids();
-    let mut id_to_j: HashMap<Id<K>, usize> = HashMap::with_capacity(ord_ids.len());
-    for (j, &id) in ord_ids.iter().enumerate() {
-        id_to_j.insert(id, j);
-    }
+/// [`eval`] that embeds a copy of `program` in the aggregate, so it can be
+/// checked with [`verify_self_contained`] without keeping the program
+/// alongside. The copy lives in memory only and is not part of the byte
+/// encoding.
+pub fn eval_self_contained<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_shares: Vec<SignShare<K, C>>,
+) -> Result<SignAggr<K, C>, ProtocolError> {
+    let mut aggr = eval(pp, program, sign_shares)?;
+    aggr.set_program(program.clone());
+    Ok(aggr)
+}
 
-    // initialize A_j = g1_gen * mu_j
-    let mut a: Vec<G1> = sign_aggr
-        .mus()
+/// [`eval`] with shares looked up by label instead of passed in program
+/// order. Errors naming the first program label that has no share.
+pub fn eval_by_label<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_shares: &HashMap<Label<K>, SignShare<K, C>>,
+) -> Result<SignAggr<K, C>, ProtocolError> {
+    let ordered = program
+        .labels()
         .iter()
-        .map(|mu_j| g1_gen() * *mu_j)
-        .collect();
+        .enumerate()
+        .map(|(i, lab)| {
+            sign_shares.get(lab).cloned().ok_or_else(|| {
+                ProtocolError::InvalidInput(format!(
+                    "no share for label {i} ({})",
+                    hex::encode(lab.to_bytes())
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    eval(pp, program, ordered)
+}
 
-    // single pass: A[j] += f_i * H(label_i)
-    for (i, lab) in program.labels().iter().enumerate() {
-        let j = *id_to_j.get(&lab.id()).ok_or_else(|| {
-            ProtocolError::InvalidInput("program label id not in signature ord_ids".to_string())
-        })?;
+/// [`eval`] for a [`SparseProgram`]: only the nonzero terms are touched.
+/// `sign_shares` is aligned with `program.labels()`, as for dense `eval`,
+/// and the result equals `eval` over `program.to_dense()`.
+pub fn eval_sparse<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &SparseProgram<K, C>,
+    sign_shares: Vec<SignShare<K, C>>,
+) -> Result<SignAggr<K, C>, ProtocolError> {
+    pp.check_program_size(program.n())?;
 
-        let f_i = program.coeffs()[i];
-        if f_i.is_zero() {
-            continue;
-        }
+    let labels = program.labels();
+    if labels.len() != sign_shares.len() {
+        return Err(ProtocolError::InvalidInput(
+            "labels/sign_shares length mismatch".to_string(),
+        ));
+    }
 
-        let h_i = hash_to_g1_with(pp.h2g1_label(), &lab.to_bytes())?;
-        // TODO: switch to MSM here also, but seems more tricky. also bench diff
-        a[j] += h_i * f_i;
+    // signer order is defined over all labels, exactly as in dense eval
+    let (ord_ids, _) = organize(labels);
+    let id_to_j: HashMap<Id<K>, usize> =
+        ord_ids.iter().enumerate().map(|(j, id)| (*id, j)).collect();
+
+    let mut gamma = Msm::<C>::with_capacity(program.terms().len());
+    let mut mus = vec![C::Scalar::zero(); ord_ids.len()];
+    for &(i, f) in program.terms() {
+        let share = &sign_shares[i];
+        gamma.add(f, *share.gamma());
+        mus[id_to_j[&labels[i].id()]] += f * share.mu();
     }
 
-    let c: GT = ord_ids.iter().enumerate().try_fold(
-        gt_one(),
-        |acc, (j, id_j)| -> Result<GT, ProtocolError> {
-            let pk = pks.get(id_j).ok_or_else(|| {
-                ProtocolError::InvalidInput("missing public key for ord_id".to_string())
+    SignAggr::new(gamma.finalize(), ord_ids, mus)
+}
+
+/// [`eval`] that, with the `debug-checks` feature and `pks` given, first
+/// runs [`verify_share`] on every input share and fails on the first invalid
+/// one instead of silently aggregating it. Without the feature this is
+/// exactly `eval` and `pks` is ignored.
+pub fn eval_checked<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_shares: Vec<SignShare<K, C>>,
+    pks: Option<&HashMap<Id<K>, PublicKey<K, C>>>,
+) -> Result<SignAggr<K, C>, ProtocolError> {
+    #[cfg(feature = "debug-checks")]
+    if let Some(pks) = pks {
+        for (i, (lab, share)) in program.labels().iter().zip(&sign_shares).enumerate() {
+            let pk = pks.get(&lab.id()).ok_or_else(|| {
+                ProtocolError::InvalidInput(format!("missing public key for term {i}"))
             })?;
-            Ok(acc * pairing(&a[j], pk.value()))
-        },
-    )?;
+            if !verify_share(pp, lab, pk, share)? {
+                return Err(ProtocolError::InvalidInput(format!(
+                    "invalid sign share for term {i}"
+                )));
+            }
+        }
+    }
 
-    // TODO: maybe switch to using `product_of_pairing` from arkworks for
-    // performance gain. in particular do criterion benchmark test to see diff
-    // ```
-    // let g1_points: Vec<_> = a.iter().collect();
-    // let g2_points: Vec<_> = ord_ids
-    //     .iter()
-    //     .map(|id| pks.get(id).unwrap().value())
-    //     .collect();
-    // let c = product_of_pairing(&g1_points, &g2_points);
-    // ```
+    #[cfg(not(feature = "debug-checks"))]
+    let _ = pks;
 
-    let lhs: GT = pairing(sign_aggr.gamma(), &g2_gen());
+    eval(pp, program, sign_shares)
+}
 
-    // ver2: $e(\gamma, g_2) = \prod_j e(A_j, \mathrm{pk}_j)$
-    Ok(lhs == c)
+/// Running `gamma` of [`eval`] after each term, in program order: entry `i`
+/// is $\sum_{k \le i} f_k \gamma_k$, so the last entry equals `eval`'s
+/// `gamma`. Inputs are validated exactly as by `eval`. Purely diagnostic,
+/// and only built with the `debug-trace` feature.
+#[cfg(feature = "debug-trace")]
+pub fn eval_trace<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_shares: Vec<SignShare<K, C>>,
+) -> Result<Vec<C::G1>, ProtocolError> {
+    let trace: Vec<C::G1> = program
+        .coeffs()
+        .iter()
+        .zip(&sign_shares)
+        .scan(C::G1::zero(), |acc, (f, share)| {
+            *acc += *share.gamma() * f;
+            Some(*acc)
+        })
+        .collect();
+    let aggr = eval(pp, program, sign_shares)?;
+    debug_assert_eq!(
+        trace.last().copied().unwrap_or_else(C::G1::zero),
+        *aggr.gamma()
+    );
+    Ok(trace)
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::types::Tag;
+/// Sum of two aggregates: the aggregate of the program that concatenates
+/// their programs, `a`'s terms first. Signers keep `a`'s order, followed by
+/// those only in `b`; a signer in both gets the sum of its `mu`s. Partial
+/// gammas survive only if both sides retained them, and the label root is
+/// dropped since it covered a different label set.
+///
+/// Combine with [`SignAggr::negate`] to subtract.
+pub fn combine<const K: usize, C: Curve>(a: &SignAggr<K, C>, b: &SignAggr<K, C>) -> SignAggr<K, C> {
+    let mut ord_ids = a.ord_ids().to_vec();
+    let mut mus = a.mus().to_vec();
+    let mut partials = a.partials().zip(b.partials()).map(|(pa, _)| pa.to_vec());
+
+    for (j_b, (id, mu)) in b.iter().enumerate() {
+        let j = match ord_ids.iter().position(|x| x == id) {
+            Some(j) => {
+                mus[j] += mu;
+                j
+            }
+            None => {
+                ord_ids.push(*id);
+                mus.push(*mu);
+                if let Some(p) = &mut partials {
+                    p.push(C::G1::zero());
+                }
+                ord_ids.len() - 1
+            }
+        };
+        if let (Some(p), Some(pb)) = (&mut partials, b.partials()) {
+            p[j] += pb[j_b];
+        }
+    }
 
-    use super::*;
+    let mut out = SignAggr::new(*a.gamma() + b.gamma(), ord_ids, mus)
+        .expect("ord_ids and mus have equal length");
+    if let Some(p) = partials {
+        out.set_partials(p);
+    }
+    out
+}
 
-    use ark_std::{UniformRand, test_rng};
+/// [`combine`] that also reports the signers present in both `a` and `b`,
+/// in `a`'s order, so callers can audit possible double counting. The
+/// merged aggregate is the same as [`combine`]'s.
+pub fn combine_strict<const K: usize, C: Curve>(
+    a: &SignAggr<K, C>,
+    b: &SignAggr<K, C>,
+) -> (SignAggr<K, C>, Vec<Id<K>>) {
+    let overlap = a
+        .ord_ids()
+        .iter()
+        .filter(|id| b.ord_ids().contains(id))
+        .copied()
+        .collect();
+    (combine(a, b), overlap)
+}
 
-    fn rand_tag<const K: usize, R: RngCore>(rng: &mut R) -> Tag<K> {
-        let mut b = [0u8; K];
-        rng.try_fill_bytes(&mut b).unwrap();
-        Tag(b)
-    }
+/// Like [`eval`], but also returns each term's weighted contribution
+/// `(label_i, f_i * mu_i)` in program order, for auditing. Summing the
+/// contributions of a signer's labels gives that signer's entry in `mus`.
+///
+/// The returned aggregate additionally retains each signer's partial gamma
+/// $\sum_{i \in j} f_i \gamma_i$, which [`diagnose_failure`] relies on.
+pub fn eval_with_breakdown<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_shares: Vec<SignShare<K, C>>,
+) -> Result<(SignAggr<K, C>, Breakdown<K, C>), ProtocolError> {
+    let breakdown = program
+        .labels()
+        .iter()
+        .zip(program.coeffs())
+        .zip(sign_shares.iter())
+        .map(|((lab, f), share)| (*lab, *f * share.mu()))
+        .collect();
 
-    mod keygen_tests {
+    let (_, groups) = organize(program.labels());
+    let coeffs = program.coeffs();
+    let partials: Vec<C::G1> = groups
+        .iter()
+        .map(|idxs| {
+            idxs.iter()
+                .filter_map(|&i| Some(*sign_shares.get(i)?.gamma() * coeffs[i]))
+                .sum()
+        })
+        .collect();
 
-        use super::*;
+    // eval rejects mismatched lengths, so the zips above were not truncated
+    let mut aggr = eval(pp, program, sign_shares)?;
+    aggr.set_partials(partials);
+    Ok((aggr, breakdown))
+}
 
-        #[test]
-        fn keygen_smoke() {
-            let pp = Params::<32>::new();
-            let mut rng = ark_std::test_rng();
-            let (_sk, pk) = keygen(&pp, &mut rng).expect("keygen failed");
-            assert_eq!(pk.id().0.len(), 32);
-        }
+/// Locates the signer responsible for a failing [`verify`], assuming exactly
+/// one signer's contribution is corrupt (a bad share or a wrong public key).
+///
+/// Needs the per-signer partial gammas retained by [`eval_with_breakdown`].
+/// Signer $j$ is consistent when $e(\gamma_j, g_2) = e(A_j, \mathrm{pk}_j)$;
+/// these equations are checked for halves of the signer set, bisecting into
+/// the failing half. Returns `None` if no partials were retained, the
+/// partials do not sum to `gamma`, every signer is consistent, more than one
+/// is not, or the inputs are malformed.
+pub fn diagnose_failure<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    sign_aggr: &SignAggr<K, C>,
+) -> Option<Id<K>> {
+    let partials = sign_aggr.partials()?;
+    if partials.iter().sum::<C::G1>() != *sign_aggr.gamma() {
+        return None;
     }
 
-    mod organize_tests {
-
-        use super::*;
+    let a = signer_points(pp, program, sign_aggr).ok()?;
+    let pks: Vec<_> = sign_aggr
+        .ord_ids()
+        .iter()
+        .map(|id| pks.get(id).map(|pk| C::prepare_g2(pk.value())))
+        .collect::<Option<_>>()?;
+
+    // e(-sum_{j in S} gamma_j, g2) * prod_{j in S} e(A_j, pk_j) == 1
+    let holds = |subset: &[usize]| {
+        let mut g1s: Vec<C::G1> = subset.iter().map(|&j| a[j]).collect();
+        let mut g2s: Vec<_> = subset.iter().map(|&j| &pks[j]).collect();
+        g1s.push(-subset.iter().map(|&j| partials[j]).sum::<C::G1>());
+        g2s.push(pp.g2_gen_prepared());
+        bool::from(gt_ct_eq(
+            &C::multi_pairing_prepared(&g1s, &g2s),
+            &C::GT::one(),
+        ))
+    };
+
+    let mut suspects: Vec<usize> = (0..a.len()).collect();
+    if holds(&suspects) {
+        return None;
+    }
+    while suspects.len() > 1 {
+        let (lo, hi) = suspects.split_at(suspects.len() / 2);
+        suspects = match (holds(lo), holds(hi)) {
+            (false, true) => lo.to_vec(),
+            (true, false) => hi.to_vec(),
+            // both halves failing means more than one culprit
+            _ => return None,
+        };
+    }
 
-        #[test]
-        fn smoke() {
-            const K: usize = 8;
-            let mut rng = test_rng();
+    Some(sign_aggr.ord_ids()[suspects[0]])
+}
 
-            // build labels with repeated ids in a known pattern:
-            // ids: A,B,A,C,B,A  => ord_ids should be [A,B,C]
-            let id_a = Id::<K>([1u8; K]);
-            let id_b = Id::<K>([2u8; K]);
-            let id_c = Id::<K>([3u8; K]);
+/// Reference computation of the `gamma` that [`eval`] should produce for
+/// `program` over messages `msgs`, computed directly from the signers' secret
+/// keys as $\sum_i f_i \cdot \mathrm{sk}_{\mathrm{id}_i} (H(\ell_i) + m_i g_1)$.
+///
+/// Intended as a test oracle; `msgs[i]` is the message signed under the
+/// `i`-th program label and `sks` must contain a key for every label id.
+pub fn expected_gamma<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    msgs: &[C::Scalar],
+    sks: &[&SecretKey<K, C>],
+) -> Result<C::G1, ProtocolError> {
+    if msgs.len() != program.n() {
+        return Err(ProtocolError::InvalidInput(
+            "msgs/program length mismatch".to_string(),
+        ));
+    }
 
-            let labels = vec![
-                Label::new(id_a, rand_tag::<K, _>(&mut rng)),
-                Label::new(id_b, rand_tag::<K, _>(&mut rng)),
-                Label::new(id_a, rand_tag::<K, _>(&mut rng)),
-                Label::new(id_c, rand_tag::<K, _>(&mut rng)),
-                Label::new(id_b, rand_tag::<K, _>(&mut rng)),
-                Label::new(id_a, rand_tag::<K, _>(&mut rng)),
-            ];
+    let by_id: HashMap<Id<K>, &C::Scalar> = sks.iter().map(|sk| (sk.id(), sk.value())).collect();
 
-            let (ord_ids, groups) = organize(&labels);
+    program
+        .coeffs()
+        .iter()
+        .zip(program.labels())
+        .zip(msgs)
+        .try_fold(C::G1::zero(), |acc, ((f, lab), m)| {
+            let x = by_id.get(&lab.id()).ok_or_else(|| {
+                ProtocolError::InvalidInput("missing secret key for label id".to_string())
+            })?;
+            let h = pp.hash_label(lab)?;
+            Ok(acc + (h + C::g1_gen() * m) * (**x * f))
+        })
+}
 
-            assert_eq!(ord_ids, vec![id_a, id_b, id_c]);
-            assert_eq!(groups.len(), ord_ids.len());
+/// Number of pairings [`verify`] evaluates for `program`: one per distinct
+/// signer plus one for $e(\gamma, g_2)$. Assumes the aggregate was produced
+/// by `eval` over the same program.
+pub fn pairing_cost<const K: usize, C: Curve>(program: &LabeledProgram<K, C>) -> usize {
+    program.num_distinct_signers() + 1
+}
 
-            // 1. groups cover all indices exactly once
-            let mut seen = vec![false; labels.len()];
-            for idxs in &groups {
-                for &i in idxs {
-                    assert!(i < labels.len());
-                    assert!(!seen[i], "index {i} appears in multiple groups");
-                    seen[i] = true;
-                }
-            }
-            assert!(
-                seen.iter().all(|b| *b == true),
-                "some indices are not covered"
-            );
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(terms = program.n(), signers = sign_aggr.ord_ids().len()),
+        ret
+    )
+)]
+/// Checks that `sign_aggr` authenticates `msg` as the output of `program`
+/// under the keys in `pks`: ver1 $\sum_j \mu_j = m$ and ver2
+/// $e(\gamma, g_2) = \prod_j e(A_j, \mathrm{pk}_j)$ over the signers in
+/// `ord_ids`.
+///
+/// A label whose signer is not in `ord_ids`, e.g. one dropped by
+/// [`EvalOptions::drop_zero_signers`], is accepted only if that signer's
+/// terms sum to the identity, as they do when all its coefficients are
+/// zero; its key then need not be in `pks`. Any other label naming a signer
+/// outside `ord_ids` is an error.
+pub fn verify<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    msg: C::Scalar,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<bool, ProtocolError> {
+    verify_in_domain(pp, &[], program, pks, msg, sign_aggr)
+}
 
-            // 2. each group corresponds to its id
-            for (j, id) in ord_ids.iter().enumerate() {
-                for &i in &groups[j] {
-                    assert_eq!(labels[i].id(), *id);
-                }
-            }
-        }
-    }
+/// [`verify`] for the affine function `y = sum_i f_i m_i + offset`, where
+/// `offset` is a public constant no one signs. The aggregate authenticates
+/// only the signed part, so this checks it against `msg - offset`.
+pub fn verify_with_offset<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    msg: C::Scalar,
+    sign_aggr: &SignAggr<K, C>,
+    offset: C::Scalar,
+) -> Result<bool, ProtocolError> {
+    verify(pp, program, pks, msg - offset, sign_aggr)
+}
 
-    mod eval_tests {
+/// [`verify`] against the program embedded by [`eval_self_contained`].
+/// Errors if the aggregate carries no program.
+pub fn verify_self_contained<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    msg: C::Scalar,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<bool, ProtocolError> {
+    let program = sign_aggr.program().ok_or_else(|| {
+        ProtocolError::InvalidInput("aggregate has no embedded program".to_string())
+    })?;
+    verify(pp, program, pks, msg, sign_aggr)
+}
 
-        use super::*;
+/// [`verify`] for shares made with [`sign_in_domain`] under `dataset`.
+pub fn verify_in_domain<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    dataset: &[u8],
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    msg: C::Scalar,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<bool, ProtocolError> {
+    pp.check_program_size(program.n())?;
 
-        #[test]
-        fn smoke() {
-            const K: usize = 8;
+    // ver1: check $\sum_j \mu_j = m$
+    let mu_sum: C::Scalar = sign_aggr.mus().iter().sum();
+    if mu_sum != msg {
+        return Ok(false);
+    }
 
-            let pp = Params::<K>::new();
-            let mut rng = test_rng();
+    verify_pairing(pp, dataset, program, pks, sign_aggr)
+}
 
-            // one user, one message, coeff=1 => eval should reproduce the same mu
-            let (sk, _pk) = keygen(&pp, &mut rng).expect("keygen failed");
+/// [`verify`] against a [`PreparedProgram`], reusing its cached label
+/// hashes instead of hashing every label again. Errors if the program was
+/// prepared under params with a different DST.
+pub fn verify_prepared<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    prepared: &PreparedProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    msg: C::Scalar,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<bool, ProtocolError> {
+    if *prepared.dst_fingerprint() != pp.dst_fingerprint() {
+        return Err(ProtocolError::InvalidInput(
+            "prepared program was built under different params".to_string(),
+        ));
+    }
+    pp.check_program_size(prepared.program().n())?;
 
-            let tag = rand_tag::<K, _>(&mut rng);
-            let label = Label::new(sk.id(), tag);
-            let msg = Scalar::rand(&mut rng);
+    // ver1
+    let mu_sum: C::Scalar = sign_aggr.mus().iter().sum();
+    if mu_sum != msg {
+        return Ok(false);
+    }
 
-            let share = sign(&pp, &sk, label, msg).expect("sign failed");
+    let points = prepared.label_points();
+    let a = accumulate_signer_points(prepared.program(), sign_aggr, |idxs| {
+        Ok(idxs.iter().map(|&i| points[i]).collect())
+    })?;
+    let c = signer_pairing_product(pks, sign_aggr.ord_ids(), &a)?;
 
-            // labeled program with n=1, f1=1
-            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label])
-                .expect("labeled program build failed");
+    // ver2
+    Ok(gt_ct_eq(&curve_pairing::<C>(sign_aggr.gamma(), &C::g2_gen()), &c).into())
+}
 
-            let aggr = eval(&pp, &program, vec![share.clone()]).expect("eval failed");
+/// [`verify`] with the public keys as a plain list in any order; they are
+/// matched to the signers with [`SignAggr::reorder_keys`].
+pub fn verify_key_list<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    pks: &[PublicKey<K, C>],
+    msg: C::Scalar,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<bool, ProtocolError> {
+    let pks: HashMap<Id<K>, PublicKey<K, C>> = sign_aggr
+        .reorder_keys(pks)?
+        .into_iter()
+        .map(|pk| (pk.id(), pk.clone()))
+        .collect();
+    verify(pp, program, &pks, msg, sign_aggr)
+}
 
-            // gamma should match share.gamma (since coeff=1)
-            assert_eq!(aggr.gamma(), share.gamma());
-            // ord_ids should contain just that signer
-            assert_eq!(aggr.ord_ids(), &[sk.id()]);
-            // mu list should contain just msg
-            assert_eq!(aggr.mus(), &[msg]);
+/// Decodes a [`DynAggr`] from `bytes` and verifies it with the inputs
+/// `verifier` holds for its `K`. Errors on an unsupported `K` or one with no
+/// registered inputs.
+pub fn verify_dyn(
+    bytes: &[u8],
+    msg: Scalar,
+    verifier: &DynVerifier<'_>,
+) -> Result<bool, ProtocolError> {
+    let missing = |k: usize| ProtocolError::InvalidInput(format!("no inputs for K = {k}"));
+    match DynAggr::from_bytes(bytes)? {
+        DynAggr::K16(aggr) => {
+            let i = verifier.k16.ok_or_else(|| missing(16))?;
+            verify(i.pp, i.program, i.pks, msg, &aggr)
+        }
+        DynAggr::K32(aggr) => {
+            let i = verifier.k32.ok_or_else(|| missing(32))?;
+            verify(i.pp, i.program, i.pks, msg, &aggr)
+        }
+    }
+}
+
+/// Verifies that `sign_aggr` authenticates the claimed output `claimed_y` of
+/// `program`: the pairing equation must hold and the aggregate `mus` must sum
+/// to `claimed_y`. A valid signature over a different value is rejected.
+pub fn verify_result<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    sign_aggr: &SignAggr<K, C>,
+    claimed_y: C::Scalar,
+) -> Result<bool, ProtocolError> {
+    if !verify_pairing(pp, &[], program, pks, sign_aggr)? {
+        return Ok(false);
+    }
+
+    // `claimed_y` may be attacker-influenced, so compare in constant time
+    let mu_sum: C::Scalar = sign_aggr.mus().iter().sum();
+    Ok(scalar_ct_eq(&mu_sum, &claimed_y).into())
+}
+
+/// ver2 of verification, independent of the claimed message.
+fn verify_pairing<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    dataset: &[u8],
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<bool, ProtocolError> {
+    let (lhs, rhs) = verify_terms_in_domain(pp, dataset, program, pks, sign_aggr)?;
+
+    // ver2: $e(\gamma, g_2) = \prod_j e(A_j, \mathrm{pk}_j)$
+    Ok(gt_ct_eq(&lhs, &rhs).into())
+}
+
+/// Both sides of the pairing equation checked by [`verify`]:
+/// `(lhs, rhs)` = $(e(\gamma, g_2), \prod_j e(A_j, \mathrm{pk}_j))$. They are
+/// equal exactly when the pairing check passes; the `mus`-sum check against
+/// the message is not part of this.
+pub fn verify_terms<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<(C::GT, C::GT), ProtocolError> {
+    verify_terms_in_domain(pp, &[], program, pks, sign_aggr)
+}
+
+/// [`verify_terms`] with every label hashed under `dataset`.
+fn verify_terms_in_domain<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    dataset: &[u8],
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<(C::GT, C::GT), ProtocolError> {
+    pp.check_program_size(program.n())?;
+
+    let a = signer_points_in_domain(pp, dataset, program, sign_aggr)?;
+    let rhs = signer_pairing_product(pks, sign_aggr.ord_ids(), &a)?;
+
+    // TODO: maybe switch to using `product_of_pairing` from arkworks for
+    // performance gain. in particular do criterion benchmark test to see diff
+    // ```
+    // let g1_points: Vec<_> = a.iter().collect();
+    // let g2_points: Vec<_> = ord_ids
+    //     .iter()
+    //     .map(|id| pks.get(id).unwrap().value())
+    //     .collect();
+    // let c = product_of_pairing(&g1_points, &g2_points);
+    // ```
+
+    let lhs = curve_pairing::<C>(sign_aggr.gamma(), &C::g2_gen());
+    Ok((lhs, rhs))
+}
+
+/// $\prod_j e(A_j, \mathrm{pk}_j)$ over the signers in `ord_ids`.
+pub(crate) fn signer_pairing_product<const K: usize, C: Curve>(
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    ord_ids: &[Id<K>],
+    a: &[C::G1],
+) -> Result<C::GT, ProtocolError> {
+    ord_ids.iter().zip(a).try_fold(
+        C::GT::one(),
+        |acc, (id_j, a_j)| -> Result<C::GT, ProtocolError> {
+            let pk = pks.get(id_j).ok_or_else(|| {
+                ProtocolError::InvalidInput("missing public key for ord_id".to_string())
+            })?;
+            Ok(acc * curve_pairing::<C>(a_j, pk.value()))
+        },
+    )
+}
+
+/// [`verify`] against prepared public keys, evaluating the whole equation
+/// as one multi-pairing $e(-\gamma, g_2) \prod_j e(A_j, \mathrm{pk}_j) = 1$.
+pub fn verify_prepared_keys<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    pks: &HashMap<Id<K>, PreparedPublicKey<K, C>>,
+    msg: C::Scalar,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<bool, ProtocolError> {
+    pp.check_program_size(program.n())?;
+
+    // ver1
+    let mu_sum: C::Scalar = sign_aggr.mus().iter().sum();
+    if mu_sum != msg {
+        return Ok(false);
+    }
+
+    let mut g1s = signer_points(pp, program, sign_aggr)?;
+    let mut g2s = sign_aggr
+        .ord_ids()
+        .iter()
+        .map(|id| {
+            pks.get(id).map(PreparedPublicKey::value).ok_or_else(|| {
+                ProtocolError::InvalidInput("missing public key for ord_id".to_string())
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    g1s.push(-*sign_aggr.gamma());
+    g2s.push(pp.g2_gen_prepared());
+
+    // ver2
+    Ok(gt_ct_eq(&C::multi_pairing_prepared(&g1s, &g2s), &C::GT::one()).into())
+}
+
+/// Computes $A_j = \mu_j g_1 + \sum_{i: \mathrm{id}_i = \mathrm{id}_j} f_i H(\ell_i)$
+/// for every signer `j` in `sign_aggr.ord_ids()`.
+fn signer_points<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    program: &LabeledProgram<K, C>,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<Vec<C::G1>, ProtocolError> {
+    signer_points_in_domain(pp, &[], program, sign_aggr)
+}
+
+/// Hash input of `label` under `dataset` (see [`sign_in_domain`]).
+fn domain_label_bytes<const K: usize>(dataset: &[u8], label: &Label<K>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(dataset.len() + 2 * K);
+    out.extend_from_slice(dataset);
+    out.extend_from_slice(&label.to_bytes());
+    out
+}
+
+/// [`signer_points`] with every label hashed under `dataset`.
+fn signer_points_in_domain<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    dataset: &[u8],
+    program: &LabeledProgram<K, C>,
+    sign_aggr: &SignAggr<K, C>,
+) -> Result<Vec<C::G1>, ProtocolError> {
+    let labels = program.labels();
+    accumulate_signer_points(program, sign_aggr, |idxs| {
+        // hash all needed labels at once
+        let label_bytes: Vec<Vec<u8>> = idxs
+            .iter()
+            .map(|&i| domain_label_bytes(dataset, &labels[i]))
+            .collect();
+        let msgs: Vec<&[u8]> = label_bytes.iter().map(Vec::as_slice).collect();
+        Ok(C::hash_to_g1_batch(pp.h2g1_label(), &msgs)?)
+    })
+}
+
+/// Shared core of [`signer_points`]: `label_points` maps the indices of the
+/// nonzero terms to their $H(\ell_i)$, in the same order.
+fn accumulate_signer_points<const K: usize, C: Curve>(
+    program: &LabeledProgram<K, C>,
+    sign_aggr: &SignAggr<K, C>,
+    label_points: impl FnOnce(&[usize]) -> Result<Vec<C::G1>, ProtocolError>,
+) -> Result<Vec<C::G1>, ProtocolError> {
+    // create id to index table
+    let ord_ids = sign_aggr.ord_ids();
+    let mut id_to_j: HashMap<Id<K>, usize> = HashMap::with_capacity(ord_ids.len());
+    for (j, &id) in ord_ids.iter().enumerate() {
+        id_to_j.insert(id, j);
+    }
+
+    // initialize A_j = g1_gen * mu_j
+    let mut a: Vec<Msm<C>> = sign_aggr
+        .mus()
+        .iter()
+        .map(|mu_j| {
+            let mut a_j = Msm::default();
+            a_j.add(*mu_j, C::g1_gen());
+            a_j
+        })
+        .collect();
+
+    // resolve signer index of every nonzero term; signers absent from the
+    // aggregate get a slot past `ord_ids` and must cancel out
+    let mut absent: HashMap<Id<K>, usize> = HashMap::new();
+    let mut terms: Vec<(usize, C::Scalar)> = Vec::with_capacity(program.n());
+    let mut idxs: Vec<usize> = Vec::with_capacity(program.n());
+    for (i, (lab, &f_i)) in program.labels().iter().zip(program.coeffs()).enumerate() {
+        // zero terms contribute nothing and need no hash
+        if f_i.is_zero() {
+            continue;
+        }
+
+        let j = match id_to_j.get(&lab.id()) {
+            Some(&j) => j,
+            None => {
+                let next = ord_ids.len() + absent.len();
+                *absent.entry(lab.id()).or_insert(next)
+            }
+        };
+
+        terms.push((j, f_i));
+        idxs.push(i);
+    }
+
+    let hs = label_points(&idxs)?;
+    a.resize_with(ord_ids.len() + absent.len(), Msm::default);
+
+    // A[j] += f_i * H(label_i)
+    for ((j, f_i), h_i) in terms.into_iter().zip(hs) {
+        a[j].add(f_i, h_i);
+    }
+
+    let mut a: Vec<C::G1> = a.into_iter().map(Msm::finalize).collect();
+    if a[ord_ids.len()..].iter().any(|a_j| !a_j.is_zero()) {
+        return Err(ProtocolError::InvalidInput(
+            "program label id not in signature ord_ids".to_string(),
+        ));
+    }
+    a.truncate(ord_ids.len());
+    Ok(a)
+}
+
+/// Verifies all `items` at once with verifier-chosen random weights $r_k$:
+/// $e(\sum_k r_k \gamma_k, g_2) = \prod_{\mathrm{pk}} e(\sum_{k,j: \mathrm{pk}_{kj} = \mathrm{pk}} r_k A_{kj}, \mathrm{pk})$.
+///
+/// Costs one pairing per distinct signer across the whole batch plus one.
+/// Returns `Ok(true)` only if every item would pass [`verify`], except with
+/// negligible probability over the choice of weights.
+pub fn batch_verify<const K: usize, C: Curve, R: RngCore>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    items: &[BatchItem<'_, K, C>],
+    rng: &mut R,
+) -> Result<bool, ProtocolError> {
+    let weights: Vec<C::Scalar> = items
+        .iter()
+        .map(|_| {
+            let mut r = C::Scalar::rand(rng);
+            while r.is_zero() {
+                r = C::Scalar::rand(rng);
+            }
+            r
+        })
+        .collect();
+    batch_verify_weighted(pp, pks, items, &weights)
+}
+
+/// Non-interactive [`batch_verify`]: the weights are derived by
+/// Fiat–Shamir from the whole batch (see [`batch_challenge_scalars`]), so the
+/// outcome is reproducible and needs no verifier randomness.
+pub fn batch_verify_fs<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    items: &[BatchItem<'_, K, C>],
+) -> Result<bool, ProtocolError> {
+    let weights = batch_challenge_scalars(pp, pks, items)?;
+    batch_verify_weighted(pp, pks, items, &weights)
+}
+
+/// [`batch_verify_fs`] under a pairing budget for latency-bounded callers.
+/// The batch costs one pairing per distinct signer across all items plus
+/// one for the combined gamma; if that exceeds `max_pairings` this returns
+/// `Ok(None)` before any pairing is computed, otherwise the verdict.
+pub fn batch_verify_with_budget<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    items: &[BatchItem<'_, K, C>],
+    max_pairings: usize,
+) -> Result<Option<bool>, ProtocolError> {
+    let signers: HashSet<&Id<K>> = items
+        .iter()
+        .flat_map(|item| item.sign_aggr.ord_ids())
+        .collect();
+    if signers.len() + 1 > max_pairings {
+        return Ok(None);
+    }
+    batch_verify_fs(pp, pks, items).map(Some)
+}
+
+/// Per-item verdicts for a batch, aligned with `items`.
+///
+/// Phase one runs the combined [`batch_verify_fs`] check over all items.
+/// Only if that fails does phase two bisect the batch, re-running the
+/// combined check on halves with the same weights until each failure is
+/// pinned to one item. Structural errors such as a missing key are returned
+/// as `Err` rather than reported per item.
+pub fn batch_verify_report<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    items: &[BatchItem<'_, K, C>],
+) -> Result<Vec<bool>, ProtocolError> {
+    let weights = batch_challenge_scalars(pp, pks, items)?;
+    let mut valid = vec![false; items.len()];
+
+    let mut stack = vec![(0..items.len()).collect::<Vec<usize>>()];
+    while let Some(subset) = stack.pop() {
+        if subset.is_empty() {
+            continue;
+        }
+        let sub_items: Vec<BatchItem<'_, K, C>> = subset.iter().map(|&k| items[k]).collect();
+        let sub_weights: Vec<C::Scalar> = subset.iter().map(|&k| weights[k]).collect();
+        if batch_verify_weighted(pp, pks, &sub_items, &sub_weights)? {
+            for k in subset {
+                valid[k] = true;
+            }
+        } else if subset.len() > 1 {
+            let (lo, hi) = subset.split_at(subset.len() / 2);
+            stack.push(hi.to_vec());
+            stack.push(lo.to_vec());
+        }
+    }
+    Ok(valid)
+}
+
+/// Fiat–Shamir weights for a batch, one per item.
+///
+/// A SHA-256 transcript binds the params, every program (via its
+/// fingerprint), claimed message, aggregate (gamma, ids and mus) and the
+/// public key of every signer involved. Weight `k` is that digest expanded
+/// with the item index and reduced into the field. Changing any input
+/// changes every weight.
+pub fn batch_challenge_scalars<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    items: &[BatchItem<'_, K, C>],
+) -> Result<Vec<C::Scalar>, ProtocolError> {
+    let mut transcript = Sha256::new()
+        .chain_update(DST_BATCH_FS)
+        .chain_update(pp.dst_fingerprint())
+        .chain_update((items.len() as u64).to_le_bytes());
+
+    let mut buf = Vec::new();
+    for item in items {
+        buf.clear();
+        buf.extend_from_slice(&item.program.fingerprint());
+        put_compressed(&mut buf, &item.msg);
+        put_compressed(&mut buf, item.sign_aggr.gamma());
+        buf.extend_from_slice(&(item.sign_aggr.ord_ids().len() as u64).to_le_bytes());
+        for (id, mu) in item.sign_aggr.ord_ids().iter().zip(item.sign_aggr.mus()) {
+            let pk = pks.get(id).ok_or_else(|| {
+                ProtocolError::InvalidInput("missing public key for ord_id".to_string())
+            })?;
+            buf.extend_from_slice(&pk.id().0);
+            put_compressed(&mut buf, pk.value());
+            put_compressed(&mut buf, mu);
+        }
+        transcript.update(&buf);
+    }
+    Ok(expand_weights(&transcript.finalize(), items.len()))
+}
+
+/// Expands a transcript digest into `n` field weights, weight `k` being
+/// 64 bytes of `SHA-256(digest || k || half)` reduced mod r.
+fn expand_weights<F: PrimeField>(digest: &[u8], n: usize) -> Vec<F> {
+    (0..n as u64)
+        .map(|k| {
+            // 64 bytes reduced mod r keeps the bias negligible
+            let mut wide = [0u8; 64];
+            for (half, chunk) in wide.chunks_mut(32).enumerate() {
+                let block = Sha256::new()
+                    .chain_update(digest)
+                    .chain_update(k.to_le_bytes())
+                    .chain_update([half as u8])
+                    .finalize();
+                chunk.copy_from_slice(&block);
+            }
+            F::from_le_bytes_mod_order(&wide)
+        })
+        .collect()
+}
+
+/// Appends the compressed encoding of `v` to a transcript buffer. For the
+/// default curve these are the bytes of the types' `to_bytes` encodings.
+fn put_compressed(buf: &mut Vec<u8>, v: &impl CanonicalSerialize) {
+    v.serialize_compressed(buf)
+        .expect("writing to a Vec cannot fail");
+}
+
+fn batch_verify_weighted<const K: usize, C: Curve>(
+    pp: &Params<K, C>,
+    pks: &HashMap<Id<K>, PublicKey<K, C>>,
+    items: &[BatchItem<'_, K, C>],
+    weights: &[C::Scalar],
+) -> Result<bool, ProtocolError> {
+    // ver1 for every item
+    for item in items {
+        let mu_sum: C::Scalar = item.sign_aggr.mus().iter().sum();
+        if mu_sum != item.msg {
+            return Ok(false);
+        }
+    }
+
+    // fold the weighted A_kj of the same signer together across items
+    let mut gamma = C::G1::zero();
+    let mut ord_pks: Vec<Id<K>> = Vec::new();
+    let mut b: HashMap<Id<K>, C::G1> = HashMap::new();
+    for (item, r) in items.iter().zip(weights) {
+        gamma += *item.sign_aggr.gamma() * r;
+        let a = signer_points(pp, item.program, item.sign_aggr)?;
+        for (id, a_j) in item.sign_aggr.ord_ids().iter().zip(a) {
+            *b.entry(*id).or_insert_with(|| {
+                ord_pks.push(*id);
+                C::G1::zero()
+            }) += a_j * r;
+        }
+    }
+
+    let c: C::GT =
+        ord_pks
+            .iter()
+            .try_fold(C::GT::one(), |acc, id| -> Result<C::GT, ProtocolError> {
+                let pk = pks.get(id).ok_or_else(|| {
+                    ProtocolError::InvalidInput("missing public key for ord_id".to_string())
+                })?;
+                Ok(acc * curve_pairing::<C>(&b[id], pk.value()))
+            })?;
+
+    let lhs: C::GT = curve_pairing::<C>(&gamma, &C::g2_gen());
+    Ok(gt_ct_eq(&lhs, &c).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        algebra::{DefaultCurve, G1, g1_gen, g2_mul_gen},
+        types::Tag,
+    };
+
+    use super::*;
+
+    use ark_std::{UniformRand, test_rng};
+
+    fn rand_tag<const K: usize, R: RngCore>(rng: &mut R) -> Tag<K> {
+        let mut b = [0u8; K];
+        rng.try_fill_bytes(&mut b).unwrap();
+        Tag(b)
+    }
+
+    fn rand_scalars<R: RngCore>(rng: &mut R, n: usize) -> Vec<Scalar> {
+        (0..n).map(|_| Scalar::rand(rng)).collect()
+    }
+
+    /// Signs `msgs[i]` under a fresh random label of `signers[i]`, returning
+    /// the labels and shares in term order.
+    fn sign_terms<const K: usize, R: RngCore>(
+        pp: &Params<K>,
+        rng: &mut R,
+        signers: &[&SecretKey<K>],
+        msgs: &[Scalar],
+    ) -> (Vec<Label<K>>, Vec<SignShare<K>>) {
+        assert_eq!(signers.len(), msgs.len());
+        signers
+            .iter()
+            .zip(msgs)
+            .map(|(sk, m)| {
+                let lab = Label::new(sk.id(), rand_tag::<K, _>(rng));
+                (lab, sign(pp, sk, lab, *m).unwrap())
+            })
+            .unzip()
+    }
+
+    mod keygen_tests {
+
+        use super::*;
+
+        #[test]
+        fn keygen_smoke() {
+            let pp = Params::<32>::new();
+            let mut rng = ark_std::test_rng();
+            let (_sk, pk) = keygen(&pp, &mut rng).expect("keygen failed");
+            assert_eq!(pk.id().0.len(), 32);
+        }
+
+        #[test]
+        fn keygen_dyn_boxed_rng() {
+            let pp = Params::<8>::new();
+            let mut boxed: Box<dyn RngCore> = Box::new(test_rng());
+            let (sk, pk) = keygen_dyn(&pp, boxed.as_mut()).unwrap();
+            assert_eq!(sk.id(), pk.id());
+            assert_eq!(pk.value(), &g2_mul_gen(sk.value()));
+
+            // same stream as the generic path
+            let (expected, _) = keygen(&pp, &mut test_rng()).unwrap();
+            let (again, _) = keygen_dyn(&pp, &mut test_rng()).unwrap();
+            assert_eq!(again, expected);
+        }
+
+        #[test]
+        fn keygen_many_distinct() {
+            let pp = Params::<8>::new();
+            let mut rng = test_rng();
+            let (sks, pks) = keygen_many(&pp, 50, &mut rng).unwrap();
+            assert_eq!(sks.len(), 50);
+            assert_eq!(pks.len(), 50);
+            for sk in &sks {
+                let pk = &pks[&sk.id()];
+                assert_eq!(pk.value(), &g2_mul_gen(sk.value()));
+            }
+        }
+
+        #[test]
+        fn keygen_many_retries_collisions() {
+            // K = 1 has only 256 ids: 64 draws collide several times on average
+            let pp = Params::<1>::new();
+            let (sks, pks) = keygen_many(&pp, 64, &mut test_rng()).unwrap();
+            assert_eq!(sks.len(), 64);
+            assert_eq!(pks.len(), 64);
+        }
+    }
+
+    mod sign_tests {
+
+        use super::*;
+
+        #[test]
+        fn foreign_label_is_rejected() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+            let own = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            let foreign = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
+
+            assert!(own.belongs_to(&sk_a.id()));
+            assert!(!foreign.belongs_to(&sk_a.id()));
+
+            assert!(matches!(
+                sign(&pp, &sk_a, foreign, Scalar::from(3)),
+                Err(ProtocolError::InvalidInput(_))
+            ));
+            let share = sign(&pp, &sk_a, own, Scalar::from(3)).unwrap();
+            assert!(verify_share(&pp, &own, &pk_a, &share).unwrap());
+        }
+
+        #[test]
+        fn signing_is_deterministic() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let msg = Scalar::rand(&mut rng);
+
+            let first = sign(&pp, &sk, label, msg).unwrap();
+            let second = sign(&pp, &sk, label, msg).unwrap();
+            assert_eq!(first.to_bytes(), second.to_bytes());
+        }
+    }
+
+    mod sign_vector_tests {
+
+        use super::*;
+
+        #[test]
+        fn components_verify_and_dot() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let pks = HashMap::from([(pk.id(), pk.clone())]);
+            let record = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let msgs = [2u64, 5, 11].map(Scalar::from);
+
+            let shares = sign_vector(&pp, &sk, record, &msgs).unwrap();
+            assert_eq!(shares.len(), 3);
+            let labels: Vec<_> = (0..3).map(|i| record.component(i)).collect();
+            assert_ne!(labels[0], labels[1]);
+            for (lab, sh) in labels.iter().zip(&shares) {
+                assert!(verify_share(&pp, lab, &pk, sh).unwrap());
+            }
+
+            let weights = [1u64, 3, 2].map(Scalar::from);
+            let program = LabeledProgram::dot(&weights, labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+            // 2 + 15 + 22
+            assert!(verify(&pp, &program, &pks, Scalar::from(39), &aggr).unwrap());
+        }
+    }
+
+    mod large_k_tests {
+
+        use super::*;
+
+        const K: usize = 64;
+
+        #[test]
+        fn round_trip_at_k64() {
+            let pp = Params::<K>::new_with_selftest().unwrap();
+            let mut rng = test_rng();
+
+            let (sks, pks) = keygen_many(&pp, 2, &mut rng).unwrap();
+
+            let mut labels = Vec::new();
+            let mut shares = Vec::new();
+            let mut y = Scalar::zero();
+            let coeffs: Vec<Scalar> = (0..3).map(|_| Scalar::rand(&mut rng)).collect();
+            for (j, f) in [0, 1, 0].into_iter().zip(&coeffs) {
+                let sk = &sks[j];
+                let lab = Label::new(sk.id(), Tag::derive(b"k64", &[j as u8, labels.len() as u8]));
+                let m = Scalar::rand(&mut rng);
+                let share = sign(&pp, sk, lab, m).unwrap();
+                assert!(verify_share(&pp, &lab, &pks[&sk.id()], &share).unwrap());
+                shares.push(share);
+                labels.push(lab);
+                y += *f * m;
+            }
+            assert_eq!(labels[0].to_bytes().len(), 2 * K);
+
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+            assert!(verify(&pp, &program, &pks, y, &aggr).unwrap());
+            assert!(!verify(&pp, &program, &pks, y + Scalar::from(1), &aggr).unwrap());
+
+            let decoded = SignAggr::<K>::from_bytes(&aggr.to_bytes()).unwrap();
+            assert_eq!(decoded, aggr);
+            let pk = &pks[&sks[1].id()];
+            assert_eq!(PublicKey::<K>::from_bytes(&pk.to_bytes()).unwrap(), *pk);
+        }
+    }
+
+    mod domain_tests {
+
+        use super::*;
+
+        #[test]
+        fn shares_are_bound_to_their_dataset() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let pks = HashMap::from([(pk.id(), pk)]);
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let msg = Scalar::rand(&mut rng);
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label]).unwrap();
+
+            let in_a = sign_in_domain(&pp, &sk, b"dataset-a", label, msg).unwrap();
+            let in_b = sign_in_domain(&pp, &sk, b"dataset-b", label, msg).unwrap();
+            assert_ne!(in_a.gamma(), in_b.gamma());
+
+            let aggr_a = eval(&pp, &program, vec![in_a]).unwrap();
+            let aggr_b = eval(&pp, &program, vec![in_b]).unwrap();
+            assert!(verify_in_domain(&pp, b"dataset-a", &program, &pks, msg, &aggr_a).unwrap());
+            assert!(verify_in_domain(&pp, b"dataset-b", &program, &pks, msg, &aggr_b).unwrap());
+            assert!(!verify_in_domain(&pp, b"dataset-b", &program, &pks, msg, &aggr_a).unwrap());
+            assert!(!verify(&pp, &program, &pks, msg, &aggr_a).unwrap());
+
+            // the empty dataset is the plain scheme
+            let plain = sign(&pp, &sk, label, msg).unwrap();
+            assert_eq!(plain, sign_in_domain(&pp, &sk, b"", label, msg).unwrap());
+        }
+    }
+
+    mod dst_rotation_tests {
+
+        use super::*;
+
+        #[test]
+        fn shares_only_verify_under_their_dst() {
+            const K: usize = 8;
+            let old = Params::<K>::new();
+            let new = old.with_new_dst(b"MKLHS-TEST:ELL->G1:V02").unwrap();
+            assert_eq!(old.dst_h2g1_label(), crate::params::DST_H2G1_LABEL);
+            assert_ne!(old.dst_fingerprint(), new.dst_fingerprint());
+            assert!(old.with_new_dst(b"").is_err());
+
+            let mut rng = test_rng();
+            let (sk, pk) = keygen(&old, &mut rng).unwrap();
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let msg = Scalar::rand(&mut rng);
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label]).unwrap();
+            let pks = HashMap::from([(pk.id(), pk)]);
+
+            let share = sign(&old, &sk, label, msg).unwrap();
+            let aggr = eval(&old, &program, vec![share]).unwrap();
+            assert!(verify(&old, &program, &pks, msg, &aggr).unwrap());
+            assert!(!verify(&new, &program, &pks, msg, &aggr).unwrap());
+        }
+    }
+
+    mod organize_tests {
+
+        use super::*;
+
+        #[test]
+        fn smoke() {
+            const K: usize = 8;
+            let mut rng = test_rng();
+
+            // build labels with repeated ids in a known pattern:
+            // ids: A,B,A,C,B,A  => ord_ids should be [A,B,C]
+            let id_a = Id::<K>([1u8; K]);
+            let id_b = Id::<K>([2u8; K]);
+            let id_c = Id::<K>([3u8; K]);
+
+            let labels = vec![
+                Label::new(id_a, rand_tag::<K, _>(&mut rng)),
+                Label::new(id_b, rand_tag::<K, _>(&mut rng)),
+                Label::new(id_a, rand_tag::<K, _>(&mut rng)),
+                Label::new(id_c, rand_tag::<K, _>(&mut rng)),
+                Label::new(id_b, rand_tag::<K, _>(&mut rng)),
+                Label::new(id_a, rand_tag::<K, _>(&mut rng)),
+            ];
+
+            let (ord_ids, groups) = organize(&labels);
+
+            assert_eq!(ord_ids, vec![id_a, id_b, id_c]);
+            assert_eq!(groups.len(), ord_ids.len());
+
+            // 1. groups cover all indices exactly once
+            let mut seen = vec![false; labels.len()];
+            for idxs in &groups {
+                for &i in idxs {
+                    assert!(i < labels.len());
+                    assert!(!seen[i], "index {i} appears in multiple groups");
+                    seen[i] = true;
+                }
+            }
+            assert!(seen.iter().all(|b| *b), "some indices are not covered");
+
+            // 2. each group corresponds to its id
+            for (j, id) in ord_ids.iter().enumerate() {
+                for &i in &groups[j] {
+                    assert_eq!(labels[i].id(), *id);
+                }
+            }
+        }
+
+        #[test]
+        fn group_shares_matches_organize() {
+            const K: usize = 8;
+            let mut rng = test_rng();
+
+            let ids = [1u8, 2, 1, 3, 2, 1, 3].map(|b| Id::<K>([b; K]));
+            let labels: Vec<_> = ids
+                .iter()
+                .map(|id| Label::new(*id, rand_tag::<K, _>(&mut rng)))
+                .collect();
+            let shares: Vec<_> = ids
+                .iter()
+                .map(|id| SignShare::<K>::new(*id, g1_gen(), Scalar::rand(&mut rng)))
+                .collect();
+
+            assert_eq!(group_shares(&shares), organize(&labels));
+            assert_eq!(group_shares::<K, DefaultCurve>(&[]), (vec![], vec![]));
+        }
+    }
+
+    mod eval_tests {
+
+        use super::*;
+
+        #[test]
+        fn smoke() {
+            const K: usize = 8;
+
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            // one user, one message, coeff=1 => eval should reproduce the same mu
+            let (sk, _pk) = keygen(&pp, &mut rng).expect("keygen failed");
+
+            let tag = rand_tag::<K, _>(&mut rng);
+            let label = Label::new(sk.id(), tag);
+            let msg = Scalar::rand(&mut rng);
+
+            let share = sign(&pp, &sk, label, msg).expect("sign failed");
+
+            // labeled program with n=1, f1=1
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label])
+                .expect("labeled program build failed");
+
+            let aggr = eval(&pp, &program, vec![share.clone()]).expect("eval failed");
+
+            // gamma should match share.gamma (since coeff=1)
+            assert_eq!(aggr.gamma(), share.gamma());
+            // ord_ids should contain just that signer
+            assert_eq!(aggr.ord_ids(), &[sk.id()]);
+            // mu list should contain just msg
+            assert_eq!(aggr.mus(), &[msg]);
+        }
+
+        #[test]
+        fn single_user_weighted_sum() {
+            // one user, 3 messages, coeffs = [2, 3, 5]
+            // expected: gamma = γ1*2 + γ2*3 + γ3*5, mu = 2*m1 + 3*m2 + 5*m3
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, _pk) = keygen(&pp, &mut rng).unwrap();
+
+            let msgs: Vec<Scalar> = (0..3).map(|_| Scalar::rand(&mut rng)).collect();
+            let tags: Vec<Tag<K>> = (0..3).map(|_| rand_tag::<K, _>(&mut rng)).collect();
+            let labels: Vec<Label<K>> = tags.iter().map(|t| Label::new(sk.id(), *t)).collect();
+
+            let shares: Vec<SignShare<K>> = labels
+                .iter()
+                .zip(msgs.iter())
+                .map(|(l, m)| sign(&pp, &sk, *l, *m).unwrap())
+                .collect();
+
+            let coeffs = vec![Scalar::from(2), Scalar::from(3), Scalar::from(5)];
+
+            let program = LabeledProgram::new(coeffs.clone(), labels).unwrap();
+            let aggr = eval(&pp, &program, shares.clone()).unwrap();
+
+            // Only one signer
+            assert_eq!(aggr.ord_ids().len(), 1);
+            assert_eq!(aggr.ord_ids()[0], sk.id());
+
+            // mu should equal weighted sum of messages
+            let expected_mu: Scalar = coeffs.iter().zip(msgs.iter()).map(|(f, m)| *f * *m).sum();
+            assert_eq!(aggr.mus()[0], expected_mu);
+
+            // gamma should equal weighted sum of gammas
+            let expected_gamma: G1 = coeffs
+                .iter()
+                .zip(shares.iter())
+                .fold(G1::zero(), |acc, (f, sh)| acc + *sh.gamma() * *f);
+            assert_eq!(*aggr.gamma(), expected_gamma);
+        }
+
+        #[test]
+        fn two_users_one_msg_each() {
+            // two users, one message each, coeffs = [1, 1]
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _pk_b) = keygen(&pp, &mut rng).unwrap();
+
+            let msg_a = Scalar::rand(&mut rng);
+            let msg_b = Scalar::rand(&mut rng);
+
+            let lab_a = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            let lab_b = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
+
+            let sh_a = sign(&pp, &sk_a, lab_a, msg_a).unwrap();
+            let sh_b = sign(&pp, &sk_b, lab_b, msg_b).unwrap();
+
+            let coeffs = vec![Scalar::from(1), Scalar::from(1)];
+            let program = LabeledProgram::new(coeffs, vec![lab_a, lab_b]).unwrap();
+            let aggr = eval(&pp, &program, vec![sh_a.clone(), sh_b.clone()]).unwrap();
+
+            // two different signers
+            assert_eq!(aggr.ord_ids().len(), 2);
+            assert_eq!(aggr.ord_ids()[0], sk_a.id());
+            assert_eq!(aggr.ord_ids()[1], sk_b.id());
+
+            // gamma should be the sum of gammas (coeff=1)
+            assert_eq!(*aggr.gamma(), *sh_a.gamma() + *sh_b.gamma());
+
+            // each mu should just be the original message (coeff=1, one msg per user)
+            assert_eq!(aggr.mus()[0], msg_a);
+            assert_eq!(aggr.mus()[1], msg_b);
+        }
+
+        #[test]
+        fn two_users_multiple_msgs() {
+            // user A signs m1, m2
+            // user B signs m3
+            // labels order: [A, B, A] => ord_ids = [A, B]
+            // coeffs = [2, 3, 4]
+            // mu_A = 2*m1 + 4*m3_A, mu_B = 3*m2_B
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let m1 = Scalar::rand(&mut rng);
+            let m2 = Scalar::rand(&mut rng);
+            let m3 = Scalar::rand(&mut rng);
+
+            let lab1 = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            let lab2 = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
+            let lab3 = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+
+            let sh1 = sign(&pp, &sk_a, lab1, m1).unwrap();
+            let sh2 = sign(&pp, &sk_b, lab2, m2).unwrap();
+            let sh3 = sign(&pp, &sk_a, lab3, m3).unwrap();
+
+            let coeffs = vec![Scalar::from(2), Scalar::from(3), Scalar::from(4)];
+            let program = LabeledProgram::new(coeffs.clone(), vec![lab1, lab2, lab3]).unwrap();
+            let aggr = eval(&pp, &program, vec![sh1.clone(), sh2.clone(), sh3.clone()]).unwrap();
+
+            // ord_ids: A first, then B
+            assert_eq!(aggr.ord_ids().len(), 2);
+            assert_eq!(aggr.ord_ids()[0], sk_a.id());
+            assert_eq!(aggr.ord_ids()[1], sk_b.id());
+
+            // mu_A = 2*m1 + 4*m3
+            let expected_mu_a = Scalar::from(2) * m1 + Scalar::from(4) * m3;
+            assert_eq!(aggr.mus()[0], expected_mu_a);
+
+            // mu_B = 3*m2
+            let expected_mu_b = Scalar::from(3) * m2;
+            assert_eq!(aggr.mus()[1], expected_mu_b);
+
+            // gamma = 2*γ1 + 3*γ2 + 4*γ3
+            let expected_gamma = *sh1.gamma() * Scalar::from(2)
+                + *sh2.gamma() * Scalar::from(3)
+                + *sh3.gamma() * Scalar::from(4);
+            assert_eq!(*aggr.gamma(), expected_gamma);
+        }
+
+        #[test]
+        fn zero_coefficients() {
+            // coeff=0 should contribute nothing
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+
+            let m1 = Scalar::rand(&mut rng);
+            let m2 = Scalar::rand(&mut rng);
+
+            let lab1 = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let lab2 = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+
+            let sh1 = sign(&pp, &sk, lab1, m1).unwrap();
+            let sh2 = sign(&pp, &sk, lab2, m2).unwrap();
+
+            let coeffs = vec![Scalar::from(7), Scalar::zero()];
+            let program = LabeledProgram::new(coeffs, vec![lab1, lab2]).unwrap();
+            let aggr = eval(&pp, &program, vec![sh1.clone(), sh2]).unwrap();
+
+            // mu should only reflect m1
+            assert_eq!(aggr.mus()[0], Scalar::from(7) * m1);
+
+            // gamma should only reflect sh1
+            assert_eq!(*aggr.gamma(), *sh1.gamma() * Scalar::from(7));
+        }
+
+        #[test]
+        fn independently_built_aggregates_are_equal() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let lab_a = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            let lab_b = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
+            let (m_a, m_b) = (Scalar::rand(&mut rng), Scalar::rand(&mut rng));
+            let coeffs = vec![Scalar::from(4), Scalar::from(6)];
+
+            // two separate sign + eval runs over the same inputs
+            let build = || {
+                let sh_a = sign(&pp, &sk_a, lab_a, m_a).unwrap();
+                let sh_b = sign(&pp, &sk_b, lab_b, m_b).unwrap();
+                let program = LabeledProgram::new(coeffs.clone(), vec![lab_a, lab_b]).unwrap();
+                (sh_a.clone(), eval(&pp, &program, vec![sh_a, sh_b]).unwrap())
+            };
+            let (sh1, aggr1) = build();
+            let (sh2, aggr2) = build();
+
+            assert_eq!(sh1, sh2);
+            assert_eq!(aggr1, aggr2);
+
+            let mut tampered = aggr2.clone();
+            tampered.mus_mut()[1] += Scalar::from(1);
+            assert_ne!(aggr1, tampered);
+        }
+
+        #[test]
+        fn length_mismatch_error() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+
+            let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let sh = sign(&pp, &sk, lab, Scalar::from(42)).unwrap();
+
+            // 2 coeffs but 1 label
+            assert!(
+                LabeledProgram::<K>::new(vec![Scalar::from(1), Scalar::from(2)], vec![lab],)
+                    .is_err()
+            );
+
+            // 1 coeff, 1 label, but 2 shares
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![lab]).unwrap();
+            assert!(eval(&pp, &program, vec![sh.clone(), sh]).is_err());
+        }
+    }
+    mod drop_zero_signers_tests {
+
+        use super::*;
+
+        #[test]
+        fn dropping_zero_signers_keeps_verification() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a), (pk_b.id(), pk_b)].into();
+
+            // B only appears with zero coefficients
+            let signers = [&sk_a, &sk_b, &sk_a, &sk_b];
+            let coeffs = vec![
+                Scalar::from(3),
+                Scalar::zero(),
+                Scalar::from(5),
+                Scalar::zero(),
+            ];
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let msg: Scalar = coeffs.iter().zip(&msgs).map(|(f, m)| *f * m).sum();
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+
+            let full = eval(&pp, &program, shares.clone()).unwrap();
+            let opts = EvalOptions {
+                drop_zero_signers: true,
+            };
+            let dropped = eval_with_options(&pp, &program, shares, opts).unwrap();
+
+            assert_eq!(full.ord_ids(), &[sk_a.id(), sk_b.id()]);
+            assert_eq!(dropped.ord_ids(), &[sk_a.id()]);
+            assert_eq!(dropped.gamma(), full.gamma());
+
+            assert!(verify(&pp, &program, &pks, msg, &full).unwrap());
+            assert!(verify(&pp, &program, &pks, msg, &dropped).unwrap());
+            let wrong = msg + Scalar::from(1);
+            assert!(!verify(&pp, &program, &pks, wrong, &dropped).unwrap());
+        }
+
+        #[test]
+        fn cancelling_signers_are_dropped() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a), (pk_b.id(), pk_b)].into();
+
+            // B's terms are f and -f on the same label: mu and partial gamma
+            // vanish, and verify accepts B's labels as cancelling
+            let lab_a = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            let lab_b = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
+            let m_a = Scalar::rand(&mut rng);
+            let m_b = Scalar::rand(&mut rng);
+            let sh_a = sign(&pp, &sk_a, lab_a, m_a).unwrap();
+            let sh_b = sign(&pp, &sk_b, lab_b, m_b).unwrap();
+            let f = Scalar::rand(&mut rng);
+            let program =
+                LabeledProgram::new(vec![Scalar::from(3), f, -f], vec![lab_a, lab_b, lab_b])
+                    .unwrap();
+
+            let opts = EvalOptions {
+                drop_zero_signers: true,
+            };
+            let aggr =
+                eval_with_options(&pp, &program, vec![sh_a, sh_b.clone(), sh_b], opts).unwrap();
+            assert_eq!(aggr.ord_ids(), &[sk_a.id()]);
+            assert!(verify(&pp, &program, &pks, Scalar::from(3) * m_a, &aggr).unwrap());
+        }
+
+        /// A label of a signer in neither `ord_ids` nor `pks` is accepted
+        /// at zero weight, but still an error at nonzero weight.
+        #[test]
+        fn absent_signer_needs_zero_weight() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a)].into();
+
+            let msgs = rand_scalars(&mut rng, 2);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b], &msgs);
+            let opts = EvalOptions {
+                drop_zero_signers: true,
+            };
+
+            let program =
+                LabeledProgram::new(vec![Scalar::from(3), Scalar::zero()], labels.clone()).unwrap();
+            let aggr = eval_with_options(&pp, &program, shares, opts).unwrap();
+            assert_eq!(aggr.ord_ids(), &[sk_a.id()]);
+            let y = Scalar::from(3) * msgs[0];
+            assert!(verify(&pp, &program, &pks, y, &aggr).unwrap());
+            assert!(!verify(&pp, &program, &pks, y + Scalar::from(1), &aggr).unwrap());
+
+            let weighted =
+                LabeledProgram::new(vec![Scalar::from(3), Scalar::from(1)], labels).unwrap();
+            assert!(verify(&pp, &weighted, &pks, y, &aggr).is_err());
+        }
+
+        #[test]
+        fn nonzero_signers_are_kept() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+            let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            // zero message but nonzero coefficient: mu is zero, gamma is not
+            let share = sign(&pp, &sk, lab, Scalar::zero()).unwrap();
+            let program = LabeledProgram::new(vec![Scalar::from(2)], vec![lab]).unwrap();
+
+            let opts = EvalOptions {
+                drop_zero_signers: true,
+            };
+            let aggr = eval_with_options(&pp, &program, vec![share], opts).unwrap();
+            assert_eq!(aggr.ord_ids(), &[sk.id()]);
+            assert_eq!(aggr.mus(), &[Scalar::zero()]);
+        }
+    }
+
+    mod integer_coeffs_tests {
+
+        use super::*;
+
+        #[test]
+        fn integer_programs_match_scalar_programs() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk.id(), pk)].into();
+
+            let msgs = [4u64, 9, 2].map(Scalar::from);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk; 3], &msgs);
+
+            let from_u64 = LabeledProgram::from_u64_coeffs(vec![2, 3, 5], labels.clone()).unwrap();
+            let scalar = LabeledProgram::new(
+                vec![Scalar::from(2), Scalar::from(3), Scalar::from(5)],
+                labels.clone(),
+            )
+            .unwrap();
+            assert_eq!(from_u64.fingerprint(), scalar.fingerprint());
+            assert_eq!(
+                eval(&pp, &from_u64, shares.clone()).unwrap(),
+                eval(&pp, &scalar, shares.clone()).unwrap()
+            );
+
+            // 2*4 - 3*9 + 5*2 = -9
+            let from_i64 = LabeledProgram::from_i64_coeffs(vec![2, -3, 5], labels).unwrap();
+            let aggr = eval(&pp, &from_i64, shares).unwrap();
+            assert_eq!(from_i64.coeffs()[1], -Scalar::from(3));
+            assert!(verify(&pp, &from_i64, &pks, -Scalar::from(9), &aggr).unwrap());
+        }
+
+        #[test]
+        fn length_mismatch_is_rejected() {
+            let lab = Label::new(Id([1u8; 8]), Tag([2u8; 8]));
+            assert!(LabeledProgram::from_u64_coeffs(vec![1, 2], vec![lab]).is_err());
+            assert!(LabeledProgram::<8>::from_i64_coeffs(vec![i64::MIN], vec![]).is_err());
+        }
+    }
+
+    mod dot_tests {
+
+        use super::*;
+
+        #[test]
+        fn evaluates_to_inner_product() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a), (pk_b.id(), pk_b)].into();
+
+            let msgs = [4u64, 7, 9].map(Scalar::from);
+            let weights = [3u64, 5, 2].map(Scalar::from);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a], &msgs);
+
+            assert!(LabeledProgram::<K>::dot(&weights[..2], labels.clone()).is_err());
+            let program = LabeledProgram::dot(&weights, labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+
+            // 3*4 + 5*7 + 2*9
+            assert_eq!(aggr.output(), Scalar::from(65));
+            assert!(verify(&pp, &program, &pks, Scalar::from(65), &aggr).unwrap());
+        }
+    }
+
+    mod average_tests {
+
+        use super::*;
+
+        #[test]
+        fn evaluates_to_field_average() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a), (pk_b.id(), pk_b)].into();
+
+            // 10, 20, 31 has no integer mean, so the field inverse matters
+            let msgs = [10u64, 20, 31].map(Scalar::from);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a], &msgs);
+
+            let program = LabeledProgram::average(labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+            let mean = Scalar::from(61) / Scalar::from(3);
+
+            assert_eq!(aggr.mus().iter().sum::<Scalar>(), mean);
+            assert_eq!(mean * Scalar::from(3), Scalar::from(61));
+            assert!(verify(&pp, &program, &pks, mean, &aggr).unwrap());
+        }
+
+        #[test]
+        fn empty_is_rejected() {
+            assert!(matches!(
+                LabeledProgram::<8>::average(vec![]),
+                Err(ProtocolError::InvalidInput(_))
+            ));
+        }
+    }
+
+    mod prune_zeros_tests {
+
+        use super::*;
+
+        #[test]
+        fn pruned_program_evaluates_identically() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+
+            // signers: A, B, A, B, A with zeros at positions 1 and 4
+            let sks = [&sk_a, &sk_b, &sk_a, &sk_b, &sk_a];
+            let coeffs = vec![
+                Scalar::from(3),
+                Scalar::zero(),
+                Scalar::from(5),
+                Scalar::from(7),
+                Scalar::zero(),
+            ];
+
+            let msgs = rand_scalars(&mut rng, sks.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &sks, &msgs);
+            let msg: Scalar = coeffs.iter().zip(&msgs).map(|(f, m)| *f * m).sum();
+
+            let program = LabeledProgram::<K>::new(coeffs, labels).unwrap();
+            let pruned = program.prune_zeros();
+            assert_eq!(pruned.n(), 3);
+            assert!(pruned.coeffs().iter().all(|f| !f.is_zero()));
+
+            let pruned_shares: Vec<SignShare<K>> = program
+                .coeffs()
+                .iter()
+                .zip(shares.iter())
+                .filter(|(f, _)| !f.is_zero())
+                .map(|(_, sh)| sh.clone())
+                .collect();
+
+            let aggr = eval(&pp, &program, shares).unwrap();
+            let aggr_pruned = eval(&pp, &pruned, pruned_shares).unwrap();
+
+            assert_eq!(aggr.gamma(), aggr_pruned.gamma());
+            assert_eq!(aggr.ord_ids(), aggr_pruned.ord_ids());
+            assert_eq!(aggr.mus(), aggr_pruned.mus());
+
+            let mut pks = HashMap::new();
+            pks.insert(pk_a.id(), pk_a);
+            pks.insert(pk_b.id(), pk_b);
+
+            assert!(verify(&pp, &program, &pks, msg, &aggr).unwrap());
+            assert!(verify(&pp, &pruned, &pks, msg, &aggr_pruned).unwrap());
+        }
+    }
+
+    mod verify_share_tests {
+
+        use super::*;
+
+        #[test]
+        fn valid_share_verifies() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let share = sign(&pp, &sk, lab, Scalar::rand(&mut rng)).unwrap();
+
+            assert!(verify_share(&pp, &lab, &pk, &share).unwrap());
+        }
+
+        #[test]
+        fn tampered_or_foreign_share_fails() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let (_, pk_other) = keygen(&pp, &mut rng).unwrap();
+            let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let share = sign(&pp, &sk, lab, Scalar::rand(&mut rng)).unwrap();
+
+            let tampered = SignShare::new(share.id(), *share.gamma() + g1_gen(), *share.mu());
+            assert!(!verify_share(&pp, &lab, &pk, &tampered).unwrap());
+
+            let wrong_mu =
+                SignShare::new(share.id(), *share.gamma(), *share.mu() + Scalar::from(1));
+            assert!(!verify_share(&pp, &lab, &pk, &wrong_mu).unwrap());
+
+            let other_lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            assert!(!verify_share(&pp, &other_lab, &pk, &share).unwrap());
+
+            assert!(!verify_share(&pp, &lab, &pk_other, &share).unwrap());
+        }
+    }
+
+    mod audit_shares_tests {
+
+        use super::*;
+
+        const K: usize = 8;
+
+        fn terms(
+            n: usize,
+        ) -> (
+            Params<K>,
+            HashMap<Id<K>, PublicKey<K>>,
+            Vec<(Label<K>, SignShare<K>)>,
+        ) {
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let keys: Vec<_> = (0..3).map(|_| keygen(&pp, &mut rng).unwrap()).collect();
+
+            let terms = (0..n)
+                .map(|i| {
+                    let sk = &keys[i % keys.len()].0;
+                    let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+                    (lab, sign(&pp, sk, lab, Scalar::rand(&mut rng)).unwrap())
+                })
+                .collect();
+            let pks = keys.into_iter().map(|(_, pk)| (pk.id(), pk)).collect();
+            (pp, pks, terms)
+        }
+
+        #[test]
+        fn all_good() {
+            let (pp, pks, terms) = terms(6);
+            let report = audit_shares(&pp, &pks, &terms).unwrap();
+            assert_eq!(report, (0..6).map(|i| (i, true)).collect::<Vec<_>>());
+            assert!(audit_shares(&pp, &pks, &[]).unwrap().is_empty());
+        }
+
+        #[test]
+        fn one_bad_share_is_flagged() {
+            let (pp, pks, mut terms) = terms(7);
+            let (label, share) = terms[4].clone();
+            let tampered =
+                SignShare::new(share.id(), *share.gamma(), *share.mu() + Scalar::from(1));
+            terms[4] = (label, tampered);
+
+            let report = audit_shares(&pp, &pks, &terms).unwrap();
+            for (i, ok) in report {
+                assert_eq!(ok, i != 4, "index {i}");
+                assert_eq!(
+                    ok,
+                    verify_share(&pp, &terms[i].0, &pks[&terms[i].0.id()], &terms[i].1).unwrap()
+                );
+            }
+        }
+
+        #[test]
+        fn structural_failures_are_flagged() {
+            let (pp, mut pks, mut terms) = terms(6);
+            // share paired with a label of another signer
+            terms[1].0 = terms[0].0;
+            // signer of index 2 (and 5) has no public key
+            pks.remove(&terms[2].0.id());
+
+            let report = audit_shares(&pp, &pks, &terms).unwrap();
+            let flags: Vec<bool> = report.into_iter().map(|(_, ok)| ok).collect();
+            assert_eq!(flags, [true, false, false, true, true, false]);
+        }
+    }
+
+    mod commitment_tests {
+
+        use super::*;
+
+        use crate::commitment::{label_inclusion_proof, verify_label_inclusion};
+
+        #[test]
+        fn inclusion_of_every_label() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            // 5 labels: an odd count exercises node promotion
+            let msgs = rand_scalars(&mut rng, 5);
+            let (labels, shares) =
+                sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a, &sk_b, &sk_a], &msgs);
+            let program = LabeledProgram::new(vec![Scalar::from(1); 5], labels).unwrap();
+
+            let aggr = eval_with_commitment(&pp, &program, shares.clone()).unwrap();
+            assert_eq!(aggr, eval(&pp, &program, shares).unwrap());
+            let root = *aggr.label_root().unwrap();
+
+            for lab in program.labels() {
+                let proof = label_inclusion_proof(program.labels(), lab).unwrap();
+                assert!(verify_label_inclusion(&root, lab, &proof));
+            }
+
+            let outsider = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            assert!(label_inclusion_proof(program.labels(), &outsider).is_none());
+            // a member's proof does not carry over to a non-member
+            let proof = label_inclusion_proof(program.labels(), &program.labels()[0]).unwrap();
+            assert!(!verify_label_inclusion(&root, &outsider, &proof));
+        }
+
+        #[test]
+        fn root_is_order_independent() {
+            const K: usize = 8;
+            let mut rng = test_rng();
+            let labels: Vec<_> = (0..4)
+                .map(|_| Label::new(Id([1u8; K]), rand_tag::<K, _>(&mut rng)))
+                .collect();
+            let mut rev = labels.clone();
+            rev.reverse();
+
+            assert_eq!(
+                crate::commitment::label_merkle_root(&labels),
+                crate::commitment::label_merkle_root(&rev)
+            );
+            assert!(
+                eval(
+                    &Params::<K>::new(),
+                    &LabeledProgram::new(vec![], vec![]).unwrap(),
+                    vec![]
+                )
+                .unwrap()
+                .label_root()
+                .is_none()
+            );
+        }
+    }
+
+    mod offset_tests {
+
+        use super::*;
+
+        #[test]
+        fn public_offset_is_added() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks = HashMap::from([(pk_a.id(), pk_a), (pk_b.id(), pk_b)]);
+
+            let msgs = [4u64, 9].map(Scalar::from);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b], &msgs);
+            let program = LabeledProgram::from_u64_coeffs(vec![3, 2], labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+
+            // y = 3*4 + 2*9 + 100
+            let offset = Scalar::from(100);
+            let y = Scalar::from(130);
+            assert!(verify_with_offset(&pp, &program, &pks, y, &aggr, offset).unwrap());
+            assert!(!verify(&pp, &program, &pks, y, &aggr).unwrap());
+            assert!(
+                !verify_with_offset(&pp, &program, &pks, y, &aggr, offset + Scalar::from(1))
+                    .unwrap()
+            );
+            // a zero offset is plain verify
+            assert!(
+                verify_with_offset(&pp, &program, &pks, Scalar::from(30), &aggr, Scalar::zero())
+                    .unwrap()
+            );
+            // negative offsets work through field negation
+            assert!(
+                verify_with_offset(
+                    &pp,
+                    &program,
+                    &pks,
+                    Scalar::from(25),
+                    &aggr,
+                    -Scalar::from(5)
+                )
+                .unwrap()
+            );
+        }
+    }
+
+    mod self_contained_tests {
+
+        use super::*;
+
+        #[test]
+        fn verifies_without_program() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks = HashMap::from([(pk_a.id(), pk_a), (pk_b.id(), pk_b)]);
+
+            let msgs = rand_scalars(&mut rng, 3);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a], &msgs);
+            let y =
+                Scalar::from(2) * msgs[0] + Scalar::from(5) * msgs[1] + Scalar::from(7) * msgs[2];
+            let program = LabeledProgram::from_u64_coeffs(vec![2, 5, 7], labels).unwrap();
+
+            let aggr = eval_self_contained(&pp, &program, shares.clone()).unwrap();
+            assert_eq!(aggr, eval(&pp, &program, shares).unwrap());
+            assert_eq!(aggr.program().unwrap().fingerprint(), program.fingerprint());
+            assert!(verify_self_contained(&pp, &pks, y, &aggr).unwrap());
+            assert!(!verify_self_contained(&pp, &pks, y + Scalar::from(1), &aggr).unwrap());
+
+            // the embedded program follows negation and survives reordering
+            assert!(verify_self_contained(&pp, &pks, -y, &aggr.negate()).unwrap());
+            assert!(verify_self_contained(&pp, &pks, y, &aggr.canonicalize()).unwrap());
+
+            // but not the byte encoding
+            let decoded = SignAggr::<K>::from_bytes(&aggr.to_bytes()).unwrap();
+            assert!(decoded.program().is_none());
+            assert!(verify_self_contained(&pp, &pks, y, &decoded).is_err());
+        }
+    }
+
+    mod eval_by_label_tests {
+
+        use super::*;
+
+        const K: usize = 8;
+
+        fn setup() -> (Params<K>, LabeledProgram<K>, Vec<SignShare<K>>) {
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let msgs = rand_scalars(&mut rng, 5);
+            let (labels, shares) =
+                sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_b, &sk_a, &sk_b], &msgs);
+            let coeffs = (0..5).map(|_| Scalar::rand(&mut rng)).collect();
+            (pp, LabeledProgram::new(coeffs, labels).unwrap(), shares)
+        }
+
+        #[test]
+        fn shuffled_map_matches_ordered_eval() {
+            let (pp, program, shares) = setup();
+
+            // insert in reverse order; map iteration order is irrelevant anyway
+            let map: HashMap<Label<K>, SignShare<K>> = program
+                .labels()
+                .iter()
+                .copied()
+                .zip(shares.iter().cloned())
+                .rev()
+                .collect();
+
+            let by_label = eval_by_label(&pp, &program, &map).unwrap();
+            assert_eq!(by_label, eval(&pp, &program, shares).unwrap());
+        }
+
+        #[test]
+        fn missing_label_is_named() {
+            let (pp, program, shares) = setup();
+            let mut map: HashMap<_, _> = program.labels().iter().copied().zip(shares).collect();
+            map.remove(&program.labels()[3]);
+
+            match eval_by_label(&pp, &program, &map) {
+                Err(ProtocolError::InvalidInput(msg)) => assert!(msg.contains("label 3")),
+                other => panic!("expected InvalidInput, got {other:?}"),
+            }
+        }
+    }
+
+    mod eval_sparse_tests {
+
+        use super::*;
+
+        #[test]
+        fn matches_dense_eval_on_mostly_zero_program() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let keys: Vec<_> = (0..3).map(|_| keygen(&pp, &mut rng).unwrap()).collect();
+
+            let n = 40;
+            let signers: Vec<_> = (0..n).map(|i| &keys[i % 3].0).collect();
+            let msgs = rand_scalars(&mut rng, n);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+
+            // only 4 of 40 coefficients are nonzero; signer 2 has none
+            let nonzero = [(0, 5u64), (3, 7), (13, 11), (37, 2)];
+            let mut coeffs = vec![Scalar::zero(); n];
+            for (i, f) in nonzero {
+                coeffs[i] = Scalar::from(f);
+            }
+            let dense = LabeledProgram::new(coeffs, labels.clone()).unwrap();
+
+            let sparse = SparseProgram::new(
+                nonzero.iter().map(|&(i, f)| (i, Scalar::from(f))).collect(),
+                labels,
+            )
+            .unwrap();
+            assert_eq!(sparse.terms().len(), 4);
+            assert_eq!(SparseProgram::from_dense(&dense).terms(), sparse.terms());
+            assert_eq!(sparse.to_dense().coeffs(), dense.coeffs());
+
+            let aggr_sparse = eval_sparse(&pp, &sparse, shares.clone()).unwrap();
+            let aggr_dense = eval(&pp, &dense, shares).unwrap();
+            assert_eq!(aggr_sparse, aggr_dense);
+
+            let pks: HashMap<_, _> = keys.iter().map(|(_, pk)| (pk.id(), pk.clone())).collect();
+            let msg: Scalar = nonzero
+                .iter()
+                .map(|&(i, f)| Scalar::from(f) * msgs[i])
+                .sum();
+            assert!(verify(&pp, &sparse.to_dense(), &pks, msg, &aggr_sparse).unwrap());
+        }
+
+        #[test]
+        fn invalid_terms_error() {
+            const K: usize = 8;
+            let labels = vec![Label::new(Id([1u8; K]), Tag([0u8; K])); 2];
+            let one = Scalar::from(1);
+
+            assert!(SparseProgram::<K>::new(vec![(2, one)], labels.clone()).is_err());
+            assert!(SparseProgram::<K>::new(vec![(1, one), (1, one)], labels.clone()).is_err());
+
+            // zero coefficients are dropped, even at out-of-range indices
+            let p = SparseProgram::<K>::new(vec![(0, one), (5, Scalar::zero())], labels).unwrap();
+            assert_eq!(p.terms(), &[(0, one)]);
+        }
+    }
+
+    mod eval_checked_tests {
+
+        use super::*;
+
+        fn setup() -> (
+            Params<8>,
+            LabeledProgram<8>,
+            Vec<SignShare<8>>,
+            HashMap<Id<8>, PublicKey<8>>,
+        ) {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+
+            let msgs = rand_scalars(&mut rng, 3);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a], &msgs);
+            let program = LabeledProgram::new(vec![Scalar::from(2); 3], labels).unwrap();
+            let pks = HashMap::from([(pk_a.id(), pk_a), (pk_b.id(), pk_b)]);
+            (pp, program, shares, pks)
+        }
+
+        #[test]
+        fn valid_shares_match_eval() {
+            let (pp, program, shares, pks) = setup();
+            let checked = eval_checked(&pp, &program, shares.clone(), Some(&pks)).unwrap();
+            assert_eq!(checked, eval(&pp, &program, shares).unwrap());
+        }
+
+        #[cfg(feature = "debug-checks")]
+        #[test]
+        fn tampered_share_is_rejected() {
+            let (pp, program, mut shares, pks) = setup();
+            let sh = &shares[1];
+            shares[1] = SignShare::new(sh.id(), *sh.gamma() + g1_gen(), *sh.mu());
+
+            let err = eval_checked(&pp, &program, shares.clone(), Some(&pks)).unwrap_err();
+            assert!(err.to_string().contains("term 1"));
+
+            // without keys there is nothing to check against
+            assert!(eval_checked(&pp, &program, shares, None).is_ok());
+        }
+
+        #[cfg(not(feature = "debug-checks"))]
+        #[test]
+        fn tampered_share_passes_without_feature() {
+            let (pp, program, mut shares, pks) = setup();
+            let sh = &shares[1];
+            shares[1] = SignShare::new(sh.id(), *sh.gamma() + g1_gen(), *sh.mu());
+
+            assert!(eval_checked(&pp, &program, shares, Some(&pks)).is_ok());
+        }
+    }
+
+    mod breakdown_tests {
+
+        use super::*;
+
+        #[test]
+        fn per_term_mus_sum_to_per_signer_mus() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let signers = [&sk_a, &sk_b, &sk_a, &sk_a, &sk_b];
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let coeffs: Vec<Scalar> = (0..5).map(|_| Scalar::rand(&mut rng)).collect();
+            let program = LabeledProgram::new(coeffs.clone(), labels.clone()).unwrap();
+
+            let (aggr, breakdown) = eval_with_breakdown(&pp, &program, shares.clone()).unwrap();
+            assert_eq!(aggr, eval(&pp, &program, shares.clone()).unwrap());
+
+            assert_eq!(breakdown.len(), 5);
+            for (i, (lab, w)) in breakdown.iter().enumerate() {
+                assert_eq!(lab.to_bytes(), labels[i].to_bytes());
+                assert_eq!(*w, coeffs[i] * shares[i].mu());
+            }
+
+            for (id, mu) in aggr.ord_ids().iter().zip(aggr.mus()) {
+                let sum: Scalar = breakdown
+                    .iter()
+                    .filter(|(lab, _)| lab.id() == *id)
+                    .map(|(_, w)| *w)
+                    .sum();
+                assert_eq!(sum, *mu);
+            }
+        }
+
+        #[test]
+        fn length_mismatch_error() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+            let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let sh = sign(&pp, &sk, lab, Scalar::from(1)).unwrap();
+
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![lab]).unwrap();
+            assert!(eval_with_breakdown(&pp, &program, vec![sh.clone(), sh]).is_err());
+        }
+
+        #[test]
+        fn partial_gammas_sum_to_gamma() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let signers = [&sk_a, &sk_b, &sk_a];
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let coeffs: Vec<Scalar> = (0..3).map(|_| Scalar::rand(&mut rng)).collect();
+            let program = LabeledProgram::new(coeffs.clone(), labels).unwrap();
+
+            let (aggr, _) = eval_with_breakdown(&pp, &program, shares.clone()).unwrap();
+            let a = aggr.partial_gamma(&sk_a.id()).unwrap();
+            let b = aggr.partial_gamma(&sk_b.id()).unwrap();
+            assert_eq!(
+                *a,
+                *shares[0].gamma() * coeffs[0] + *shares[2].gamma() * coeffs[2]
+            );
+            assert_eq!(*b, *shares[1].gamma() * coeffs[1]);
+            assert_eq!(*a + *b, *aggr.gamma());
+            assert!(aggr.partial_gamma(&Id([0u8; K])).is_none());
+
+            let plain = eval(&pp, &program, shares).unwrap();
+            assert!(plain.partial_gamma(&sk_a.id()).is_none());
+        }
+    }
+
+    #[cfg(feature = "debug-trace")]
+    mod eval_trace_tests {
+
+        use super::*;
+
+        #[test]
+        fn last_entry_is_eval_gamma() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let msgs = rand_scalars(&mut rng, 3);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a], &msgs);
+            let coeffs: Vec<Scalar> = (0..3).map(|_| Scalar::rand(&mut rng)).collect();
+            let program = LabeledProgram::new(coeffs.clone(), labels).unwrap();
+
+            let trace = eval_trace(&pp, &program, shares.clone()).unwrap();
+            assert_eq!(trace.len(), 3);
+            assert_eq!(trace[0], *shares[0].gamma() * coeffs[0]);
+            let aggr = eval(&pp, &program, shares.clone()).unwrap();
+            assert_eq!(trace[2], *aggr.gamma());
+
+            assert!(eval_trace(&pp, &program, shares[..2].to_vec()).is_err());
+        }
+    }
+
+    mod combine_tests {
+
+        use super::*;
+
+        #[test]
+        fn strict_reports_overlap() {
+            let ids: Vec<Id<8>> = (1..=4).map(|i| Id([i; 8])).collect();
+            let aggr = |js: &[usize]| {
+                SignAggr::<8>::new(
+                    G1::zero(),
+                    js.iter().map(|&j| ids[j]).collect(),
+                    js.iter().map(|&j| Scalar::from(j as u64 + 1)).collect(),
+                )
+                .unwrap()
+            };
+
+            let (a, b) = (aggr(&[2, 0, 1]), aggr(&[1, 3, 2]));
+            let (merged, overlap) = combine_strict(&a, &b);
+            assert_eq!(merged, combine(&a, &b));
+            assert_eq!(overlap, vec![ids[2], ids[1]]);
+
+            let (merged, overlap) = combine_strict(&aggr(&[0, 1]), &aggr(&[2, 3]));
+            assert_eq!(merged.num_signers(), 4);
+            assert!(overlap.is_empty());
+        }
+
+        #[test]
+        fn any_two_aggregates_are_compatible() {
+            let ids: Vec<Id<8>> = (1..=3).map(|i| Id([i; 8])).collect();
+            let aggr = |js: &[usize]| {
+                SignAggr::<8>::new(
+                    g1_gen() * Scalar::from(js.len() as u64),
+                    js.iter().map(|&j| ids[j]).collect(),
+                    js.iter().map(|&j| Scalar::from(j as u64 + 1)).collect(),
+                )
+                .unwrap()
+            };
+
+            // disjoint, overlapping in reverse order, and empty signer lists
+            let (a, b, c) = (aggr(&[0, 1]), aggr(&[2]), aggr(&[1, 0]));
+            let empty = aggr(&[]);
+            for (x, y) in [(&a, &b), (&a, &c), (&c, &a), (&a, &empty), (&empty, &a)] {
+                assert!(x.compatible_with(y));
+                assert_eq!(combine(x, y).output(), x.output() + y.output());
+            }
+        }
+
+        #[test]
+        fn concatenated_program_verifies() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a), (pk_b.id(), pk_b)].into();
+
+            let msgs = rand_scalars(&mut rng, 3);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_a, &sk_b], &msgs);
+            let coeffs: Vec<Scalar> = (0..3).map(|_| Scalar::rand(&mut rng)).collect();
+
+            let p1 = LabeledProgram::new(coeffs[..1].to_vec(), labels[..1].to_vec()).unwrap();
+            let p2 = LabeledProgram::new(coeffs[1..].to_vec(), labels[1..].to_vec()).unwrap();
+            let a1 = eval(&pp, &p1, shares[..1].to_vec()).unwrap();
+            let a2 = eval(&pp, &p2, shares[1..].to_vec()).unwrap();
+
+            let full = LabeledProgram::new(coeffs.clone(), labels).unwrap();
+            let combined = combine(&a1, &a2);
+            assert_eq!(combined, eval(&pp, &full, shares).unwrap());
+            let y: Scalar = coeffs.iter().zip(&msgs).map(|(f, m)| *f * m).sum();
+            assert!(verify(&pp, &full, &pks, y, &combined).unwrap());
+        }
+
+        #[test]
+        fn chunked_eval_combines_to_whole() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let keys: Vec<_> = (0..3).map(|_| keygen(&pp, &mut rng).unwrap()).collect();
+            let pks: HashMap<_, _> = keys.iter().map(|(_, pk)| (pk.id(), pk.clone())).collect();
+
+            let signers = [0, 1, 0, 2, 1, 2, 0].map(|s| &keys[s].0);
+            let coeffs = rand_scalars(&mut rng, signers.len());
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let y: Scalar = coeffs.iter().zip(&msgs).map(|(f, m)| *f * m).sum();
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let whole = eval(&pp, &program, shares.clone()).unwrap();
+
+            assert!(program.chunk(0).is_err());
+            let chunks = program.chunk(3).unwrap();
+            assert_eq!(
+                chunks.iter().map(LabeledProgram::n).collect::<Vec<_>>(),
+                [3, 3, 1]
+            );
+            let combined = chunks
+                .iter()
+                .zip(shares.chunks(3))
+                .map(|(p, sh)| eval(&pp, p, sh.to_vec()).unwrap())
+                .reduce(|acc, a| combine(&acc, &a))
+                .unwrap();
+            assert_eq!(combined, whole);
+            assert!(verify(&pp, &program, &pks, y, &combined).unwrap());
+        }
+
+        #[test]
+        fn subtracting_itself_is_identity() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let pks = HashMap::from([(pk.id(), pk)]);
+            let labels: Vec<_> = (0..2)
+                .map(|_| Label::new(sk.id(), rand_tag::<K, _>(&mut rng)))
+                .collect();
+            let shares: Vec<_> = labels
+                .iter()
+                .map(|lab| sign(&pp, &sk, *lab, Scalar::rand(&mut rng)).unwrap())
+                .collect();
+            let program =
+                LabeledProgram::new(vec![Scalar::from(3), Scalar::from(4)], labels.clone())
+                    .unwrap();
+            let a = eval(&pp, &program, shares).unwrap();
+
+            let diff = combine(&a, &a.negate());
+            assert!(diff.is_identity());
+            assert_eq!(a.negate().negate(), a);
+
+            let zero = LabeledProgram::new(vec![Scalar::zero(); 2], labels).unwrap();
+            assert!(verify(&pp, &zero, &pks, Scalar::zero(), &diff).unwrap());
+        }
+
+        #[test]
+        fn partials_are_merged() {
+            let (a, [id_a, id_b], _) = sign_aggr_accessor_tests::two_signer_aggr();
+            assert!(combine(&a, &a).partials().is_none());
+
+            let mut with_partials = a.clone();
+            with_partials.set_partials(vec![*a.gamma(), G1::zero()]);
+            let doubled = combine(&with_partials, &with_partials);
+            assert_eq!(
+                doubled.partial_gamma(&id_a),
+                Some(&(*a.gamma() + a.gamma()))
+            );
+            assert_eq!(doubled.partial_gamma(&id_b), Some(&G1::zero()));
+        }
+    }
+
+    mod project_tests {
+
+        use super::*;
+
+        #[test]
+        fn projection_verifies_against_program_slice() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+            // the verifier only holds A's key
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a)].into();
+
+            let coeffs = rand_scalars(&mut rng, 4);
+            let msgs = rand_scalars(&mut rng, 4);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a, &sk_b], &msgs);
+            let msg_a = coeffs[0] * msgs[0] + coeffs[2] * msgs[2];
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let (aggr, _) = eval_with_breakdown(&pp, &program, shares).unwrap();
+
+            let sub = aggr.project(&[sk_a.id()]).unwrap();
+            assert_eq!(sub.ord_ids(), &[sk_a.id()]);
+            assert_eq!(sub.mus(), &[msg_a]);
+
+            let (coeffs, labels) = program
+                .coeffs()
+                .iter()
+                .zip(program.labels())
+                .filter(|(_, lab)| lab.belongs_to(&sk_a.id()))
+                .map(|(f, lab)| (*f, *lab))
+                .unzip();
+            let slice = LabeledProgram::new(coeffs, labels).unwrap();
+            assert!(verify(&pp, &slice, &pks, msg_a, &sub).unwrap());
+            assert!(!verify(&pp, &slice, &pks, msg_a + Scalar::from(1), &sub).unwrap());
+
+            // projecting onto everyone reproduces the aggregate
+            assert_eq!(aggr.project(&[sk_b.id(), sk_a.id()]).unwrap(), aggr);
+        }
+
+        #[test]
+        fn requires_retained_breakdown() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+            let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let share = sign(&pp, &sk, lab, Scalar::from(4)).unwrap();
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![lab]).unwrap();
+
+            let plain = eval(&pp, &program, vec![share.clone()]).unwrap();
+            assert!(plain.project(&[sk.id()]).is_none());
+
+            let (aggr, _) = eval_with_breakdown(&pp, &program, vec![share]).unwrap();
+            assert!(aggr.project(&[sk.id()]).is_some());
+            assert!(aggr.project(&[Id([0u8; K])]).is_none());
+        }
+    }
+
+    mod diagnose_failure_tests {
+
+        use super::*;
+
+        use crate::algebra::G2;
+
+        const K: usize = 8;
+
+        /// Breakdown aggregate over four signers with two terms each.
+        fn setup() -> (
+            Params<K>,
+            LabeledProgram<K>,
+            HashMap<Id<K>, PublicKey<K>>,
+            Scalar,
+            SignAggr<K>,
+            Vec<Id<K>>,
+        ) {
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let keys: Vec<_> = (0..4).map(|_| keygen(&pp, &mut rng).unwrap()).collect();
+
+            let signers: Vec<_> = keys.iter().chain(&keys).map(|(sk, _)| sk).collect();
+            let coeffs = rand_scalars(&mut rng, signers.len());
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let msg: Scalar = coeffs.iter().zip(&msgs).map(|(f, m)| *f * m).sum();
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let (aggr, _) = eval_with_breakdown(&pp, &program, shares).unwrap();
+            let ids = keys.iter().map(|(sk, _)| sk.id()).collect();
+            let pks = keys.into_iter().map(|(_, pk)| (pk.id(), pk)).collect();
+            (pp, program, pks, msg, aggr, ids)
+        }
+
+        #[test]
+        fn corrupt_public_key_is_named() {
+            let (pp, program, mut pks, msg, aggr, ids) = setup();
+            assert!(verify(&pp, &program, &pks, msg, &aggr).unwrap());
+            assert_eq!(diagnose_failure(&pp, &program, &pks, &aggr), None);
+
+            for culprit in &ids {
+                let mut pks = pks.clone();
+                let bogus = G2::rand(&mut test_rng());
+                pks.insert(*culprit, PublicKey::new(*culprit, bogus));
+
+                assert!(!verify(&pp, &program, &pks, msg, &aggr).unwrap());
+                assert_eq!(diagnose_failure(&pp, &program, &pks, &aggr), Some(*culprit));
+            }
+
+            // two corrupt keys cannot be localized
+            for id in &ids[..2] {
+                pks.insert(*id, PublicKey::new(*id, G2::rand(&mut test_rng())));
+            }
+            assert_eq!(diagnose_failure(&pp, &program, &pks, &aggr), None);
+        }
+
+        #[test]
+        fn without_breakdown_returns_none() {
+            let (pp, program, mut pks, _, aggr, ids) = setup();
+            let plain =
+                SignAggr::new(*aggr.gamma(), aggr.ord_ids().to_vec(), aggr.mus().to_vec()).unwrap();
+            pks.insert(ids[1], PublicKey::new(ids[1], G2::rand(&mut test_rng())));
+
+            assert_eq!(diagnose_failure(&pp, &program, &pks, &plain), None);
+        }
+    }
+
+    mod expected_gamma_tests {
+
+        use super::*;
+
+        #[test]
+        fn eval_matches_oracle_multi_signer() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_c, _) = keygen(&pp, &mut rng).unwrap();
+
+            let signers = [&sk_a, &sk_b, &sk_c, &sk_a, &sk_b];
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+
+            let coeffs: Vec<Scalar> = (0..5).map(|_| Scalar::rand(&mut rng)).collect();
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+
+            let oracle = expected_gamma(&pp, &program, &msgs, &[&sk_a, &sk_b, &sk_c]).unwrap();
+            assert_eq!(*aggr.gamma(), oracle);
+        }
+
+        #[test]
+        fn missing_secret_key_errors() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let lab = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![lab]).unwrap();
+
+            assert!(expected_gamma(&pp, &program, &[Scalar::from(1)], &[&sk_a]).is_err());
+            assert!(expected_gamma(&pp, &program, &[], &[&sk_b]).is_err());
+        }
+    }
+
+    mod add_term_tests {
+
+        use super::*;
+
+        #[test]
+        fn term_by_term_matches_eval() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let signers = [&sk_b, &sk_a, &sk_b, &sk_a];
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let coeffs: Vec<Scalar> = (0..4).map(|_| Scalar::rand(&mut rng)).collect();
+
+            let mut aggr = SignAggr::new(G1::zero(), Vec::new(), Vec::new()).unwrap();
+            for ((f, lab), sh) in coeffs.iter().zip(labels.iter()).zip(shares.iter()) {
+                aggr.add_term(*f, lab.id(), sh).unwrap();
+            }
+
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let expected = eval(&pp, &program, shares).unwrap();
+
+            assert_eq!(aggr, expected);
+        }
+
+        #[test]
+        fn canonicalize_makes_orderings_equal() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let keys: Vec<_> = (0..3).map(|_| keygen(&pp, &mut rng).unwrap()).collect();
+            let pks: HashMap<_, _> = keys.iter().map(|(_, pk)| (pk.id(), pk.clone())).collect();
+
+            let mut terms = Vec::new();
+            let mut msg = Scalar::zero();
+            for (sk, _) in &keys {
+                let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+                let (m, f) = (Scalar::rand(&mut rng), Scalar::rand(&mut rng));
+                terms.push((f, lab, sign(&pp, sk, lab, m).unwrap()));
+                msg += f * m;
+            }
+
+            let build = |order: &[usize]| {
+                let mut aggr = SignAggr::new(G1::zero(), Vec::new(), Vec::new()).unwrap();
+                for &i in order {
+                    let (f, lab, sh) = &terms[i];
+                    aggr.add_term(*f, lab.id(), sh).unwrap();
+                }
+                aggr
+            };
+            let x = build(&[0, 1, 2]);
+            let y = build(&[2, 0, 1]);
+            assert_ne!(x, y);
+
+            let (cx, cy) = (x.canonicalize(), y.canonicalize());
+            assert_eq!(cx, cy);
+            assert_eq!(cx.gamma(), x.gamma());
+            assert!(cx.ord_ids().windows(2).all(|w| w[0].0 < w[1].0));
+
+            let (coeffs, labels) = terms.iter().map(|(f, lab, _)| (*f, *lab)).unzip();
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            assert!(verify(&pp, &program, &pks, msg, &cx).unwrap());
+        }
+
+        #[test]
+        fn mismatched_id_errors() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let lab = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            let sh = sign(&pp, &sk_a, lab, Scalar::from(5)).unwrap();
+
+            let mut aggr = SignAggr::new(G1::zero(), Vec::new(), Vec::new()).unwrap();
+            assert!(aggr.add_term(Scalar::from(1), sk_b.id(), &sh).is_err());
+            assert!(aggr.ord_ids().is_empty());
+            assert!(aggr.gamma().is_zero());
+        }
+
+        #[test]
+        fn drops_label_root() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+            let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let sh = sign(&pp, &sk, lab, Scalar::from(5)).unwrap();
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![lab]).unwrap();
+
+            let mut aggr = eval_with_commitment(&pp, &program, vec![sh.clone()]).unwrap();
+            assert!(aggr.label_root().is_some());
+            aggr.add_term(Scalar::from(2), sk.id(), &sh).unwrap();
+            assert!(aggr.label_root().is_none());
+        }
+
+        #[test]
+        fn checkpoint_and_resume_matches_eval() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_c, _) = keygen(&pp, &mut rng).unwrap();
+
+            let signers = [&sk_a, &sk_b, &sk_a, &sk_c, &sk_b, &sk_c];
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let coeffs: Vec<Scalar> = (0..6).map(|_| Scalar::rand(&mut rng)).collect();
+            let terms: Vec<_> = coeffs.iter().zip(&labels).zip(&shares).collect();
+
+            // first half, then checkpoint; c has not been seen yet
+            let mut aggr = SignAggr::new(G1::zero(), Vec::new(), Vec::new()).unwrap();
+            for ((f, lab), sh) in &terms[..3] {
+                aggr.add_term(**f, lab.id(), sh).unwrap();
+            }
+            let checkpoint = aggr.to_bytes();
+            drop(aggr);
+
+            let mut resumed = SignAggr::<K>::from_bytes(&checkpoint).unwrap();
+            for ((f, lab), sh) in &terms[3..] {
+                resumed.add_term(**f, lab.id(), sh).unwrap();
+            }
+
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let expected = eval(&pp, &program, shares).unwrap();
+
+            assert_eq!(resumed, expected);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    mod tracing_tests {
+
+        use super::*;
+
+        use std::sync::{Arc, Mutex};
+        use tracing::{
+            Event, Metadata, Subscriber,
+            field::{Field, Visit},
+            span,
+        };
+
+        type Fields = Vec<(&'static str, String, u64)>;
+
+        /// Records `(span name, field, value)` for every u64 field set on a
+        /// span, whether at creation or via `Span::record`.
+        #[derive(Clone, Default)]
+        struct Capture {
+            spans: Arc<Mutex<Vec<&'static Metadata<'static>>>>,
+            fields: Arc<Mutex<Fields>>,
+        }
+
+        struct U64Visitor<'a> {
+            span: &'static str,
+            out: &'a mut Fields,
+        }
+
+        impl Visit for U64Visitor<'_> {
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                self.out.push((self.span, field.name().to_string(), value));
+            }
+
+            fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+        }
+
+        impl Subscriber for Capture {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+                let mut spans = self.spans.lock().unwrap();
+                spans.push(attrs.metadata());
+                attrs.record(&mut U64Visitor {
+                    span: attrs.metadata().name(),
+                    out: &mut self.fields.lock().unwrap(),
+                });
+                span::Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+                let meta = self.spans.lock().unwrap()[id.into_u64() as usize - 1];
+                values.record(&mut U64Visitor {
+                    span: meta.name(),
+                    out: &mut self.fields.lock().unwrap(),
+                });
+            }
+
+            fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+            fn event(&self, _event: &Event<'_>) {}
+
+            fn enter(&self, _span: &span::Id) {}
+
+            fn exit(&self, _span: &span::Id) {}
         }
 
         #[test]
-        fn single_user_weighted_sum() {
-            // one user, 3 messages, coeffs = [2, 3, 5]
-            // expected: gamma = γ1*2 + γ2*3 + γ3*5, mu = 2*m1 + 3*m2 + 5*m3
+        fn eval_span_records_signer_count() {
             const K: usize = 8;
             let pp = Params::<K>::new();
             let mut rng = test_rng();
 
-            let (sk, _pk) = keygen(&pp, &mut rng).unwrap();
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
 
-            let msgs: Vec<Scalar> = (0..3).map(|_| Scalar::rand(&mut rng)).collect();
-            let tags: Vec<Tag<K>> = (0..3).map(|_| rand_tag::<K, _>(&mut rng)).collect();
-            let labels: Vec<Label<K>> = tags.iter().map(|t| Label::new(sk.id(), *t)).collect();
+            let signers = [&sk_a, &sk_b, &sk_a];
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let program = LabeledProgram::new(vec![Scalar::from(1); 3], labels).unwrap();
 
-            let shares: Vec<SignShare<K>> = labels
-                .iter()
-                .zip(msgs.iter())
-                .map(|(l, m)| sign(&pp, &sk, *l, *m).unwrap())
-                .collect();
+            let capture = Capture::default();
+            tracing::subscriber::with_default(capture.clone(), || {
+                eval(&pp, &program, shares).unwrap();
+            });
 
-            let coeffs = vec![Scalar::from(2), Scalar::from(3), Scalar::from(5)];
+            let fields = capture.fields.lock().unwrap();
+            assert!(fields.contains(&("eval", "terms".to_string(), 3)));
+            assert!(fields.contains(&("eval", "signers".to_string(), 2)));
+        }
+    }
 
-            let program = LabeledProgram::new(coeffs.clone(), labels).unwrap();
-            let aggr = eval(&pp, &program, shares.clone()).unwrap();
+    mod sign_aggr_accessor_tests {
 
-            // Only one signer
-            assert_eq!(aggr.ord_ids().len(), 1);
-            assert_eq!(aggr.ord_ids()[0], sk.id());
+        use super::*;
 
-            // mu should equal weighted sum of messages
-            let expected_mu: Scalar = coeffs.iter().zip(msgs.iter()).map(|(f, m)| *f * *m).sum();
-            assert_eq!(aggr.mus()[0], expected_mu);
+        /// Aggregate over A, B, A with coeffs 1, 2, 3; returns it with the
+        /// two signer ids and their expected mus.
+        pub(super) fn two_signer_aggr() -> (SignAggr<8>, [Id<8>; 2], [Scalar; 2]) {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
 
-            // gamma should equal weighted sum of gammas
-            let expected_gamma: G1 = coeffs
-                .iter()
-                .zip(shares.iter())
-                .fold(G1::zero(), |acc, (f, sh)| acc + *sh.gamma() * *f);
-            assert_eq!(*aggr.gamma(), expected_gamma);
+            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+
+            let msgs = rand_scalars(&mut rng, 3);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a], &msgs);
+            let coeffs = vec![Scalar::from(1), Scalar::from(2), Scalar::from(3)];
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+
+            let mu_a = msgs[0] + Scalar::from(3) * msgs[2];
+            let mu_b = Scalar::from(2) * msgs[1];
+            (aggr, [sk_a.id(), sk_b.id()], [mu_a, mu_b])
         }
 
         #[test]
-        fn two_users_one_msg_each() {
-            // two users, one message each, coeffs = [1, 1]
+        fn mu_for_two_signers() {
+            let (aggr, [id_a, id_b], [mu_a, mu_b]) = two_signer_aggr();
+
+            assert_eq!(aggr.mu_for(&id_a), Some(&mu_a));
+            assert_eq!(aggr.mu_for(&id_b), Some(&mu_b));
+            assert_eq!(aggr.mu_for(&Id([0u8; 8])), None);
+        }
+
+        #[test]
+        fn identity_only_for_trivial_aggregates() {
+            let pp = Params::<8>::new();
+            let empty = LabeledProgram::<8>::new(Vec::new(), Vec::new()).unwrap();
+            assert!(eval(&pp, &empty, Vec::new()).unwrap().is_identity());
+
+            let (aggr, _, _) = two_signer_aggr();
+            assert!(!aggr.is_identity());
+        }
+
+        #[test]
+        fn output_is_weighted_sum() {
+            let (aggr, _, [mu_a, mu_b]) = two_signer_aggr();
+            assert_eq!(aggr.output(), mu_a + mu_b);
+        }
+
+        #[test]
+        fn reorder_keys_follows_ord_ids() {
             const K: usize = 8;
             let pp = Params::<K>::new();
             let mut rng = test_rng();
 
-            let (sk_a, _pk_a) = keygen(&pp, &mut rng).unwrap();
-            let (sk_b, _pk_b) = keygen(&pp, &mut rng).unwrap();
+            let keys: Vec<_> = (0..3).map(|_| keygen(&pp, &mut rng).unwrap()).collect();
+            let signers: Vec<_> = keys.iter().map(|(sk, _)| sk).collect();
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let msg: Scalar = msgs.iter().sum();
+            let program = LabeledProgram::new(vec![Scalar::from(1); 3], labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+
+            let mut pks: Vec<PublicKey<K>> = keys.iter().map(|(_, pk)| pk.clone()).collect();
+            pks.reverse();
+
+            let ordered = aggr.reorder_keys(&pks).unwrap();
+            let ids: Vec<_> = ordered.iter().map(|pk| pk.id()).collect();
+            assert_eq!(ids, aggr.ord_ids());
+            assert!(verify_key_list(&pp, &program, &pks, msg, &aggr).unwrap());
+
+            let missing = &pks[1..];
+            assert!(aggr.reorder_keys(missing).is_err());
+            let mut dup = pks.clone();
+            dup.push(pks[0].clone());
+            assert!(aggr.reorder_keys(&dup).is_err());
+        }
 
-            let msg_a = Scalar::rand(&mut rng);
-            let msg_b = Scalar::rand(&mut rng);
+        #[test]
+        fn iter_matches_mu_for() {
+            let (aggr, ids, _) = two_signer_aggr();
 
-            let lab_a = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
-            let lab_b = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
+            let map: HashMap<Id<8>, Scalar> = aggr.iter().map(|(id, mu)| (*id, *mu)).collect();
+            assert_eq!(map.len(), 2);
+            for id in ids {
+                assert_eq!(map.get(&id), aggr.mu_for(&id));
+            }
+            assert_eq!(
+                aggr.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+                aggr.ord_ids()
+            );
+        }
 
-            let sh_a = sign(&pp, &sk_a, lab_a, msg_a).unwrap();
-            let sh_b = sign(&pp, &sk_b, lab_b, msg_b).unwrap();
+        #[test]
+        fn signer_counts() {
+            let (aggr, _, _) = two_signer_aggr();
+            assert_eq!(aggr.num_signers(), 2);
+            assert_eq!(aggr.num_signers(), aggr.ord_ids().len());
 
-            let coeffs = vec![Scalar::from(1), Scalar::from(1)];
-            let program = LabeledProgram::new(coeffs, vec![lab_a, lab_b]).unwrap();
-            let aggr = eval(&pp, &program, vec![sh_a.clone(), sh_b.clone()]).unwrap();
+            const K: usize = 8;
+            let mut rng = test_rng();
+            let labels: Vec<_> = [1u8, 2, 1, 3, 2]
+                .map(|b| Label::new(Id::<K>([b; K]), rand_tag::<K, _>(&mut rng)))
+                .into();
+            let program = LabeledProgram::<K>::new(vec![Scalar::from(1); 5], labels).unwrap();
+            assert_eq!(program.n(), 5);
+            assert_eq!(program.num_distinct_signers(), 3);
+        }
+    }
 
-            // two different signers
-            assert_eq!(aggr.ord_ids().len(), 2);
-            assert_eq!(aggr.ord_ids()[0], sk_a.id());
-            assert_eq!(aggr.ord_ids()[1], sk_b.id());
+    mod verify_tests {
 
-            // gamma should be the sum of gammas (coeff=1)
-            assert_eq!(*aggr.gamma(), *sh_a.gamma() + *sh_b.gamma());
+        use super::*;
+        use crate::algebra::Bls12_381Backend;
+
+        /// Runs the generic test body once per curve backend compiled in.
+        macro_rules! on_each_backend {
+            ($test:ident) => {{
+                $test::<Bls12_381Backend>();
+                #[cfg(feature = "blst")]
+                $test::<crate::algebra::BlstBackend>();
+            }};
+        }
 
-            // each mu should just be the original message (coeff=1, one msg per user)
-            assert_eq!(aggr.mus()[0], msg_a);
-            assert_eq!(aggr.mus()[1], msg_b);
+        fn smoke_on<C: Curve>() {
+            const K: usize = 8;
+
+            let pp = Params::<K, C>::new();
+            let mut rng = test_rng();
+
+            // one signer
+            let (sk, pk) = keygen(&pp, &mut rng).expect("keygen failed");
+
+            let msg = C::Scalar::rand(&mut rng);
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+
+            let share = sign(&pp, &sk, label, msg).expect("sign failed");
+
+            // trivial linear program: f = 1
+            let program = LabeledProgram::new(vec![C::Scalar::from(1u64)], vec![label])
+                .expect("program build failed");
+
+            let aggr = eval(&pp, &program, vec![share]).expect("eval failed");
+
+            let mut pks = HashMap::new();
+            pks.insert(pk.id(), pk);
+
+            let result = verify(&pp, &program, &pks, msg, &aggr).expect("verify errored");
+
+            assert!(result);
         }
 
         #[test]
-        fn two_users_multiple_msgs() {
-            // user A signs m1, m2
-            // user B signs m3
-            // labels order: [A, B, A] => ord_ids = [A, B]
-            // coeffs = [2, 3, 4]
-            // mu_A = 2*m1 + 4*m3_A, mu_B = 3*m2_B
+        fn smoke() {
+            on_each_backend!(smoke_on);
+        }
+
+        fn fails_on_wrong_message_on<C: Curve>() {
             const K: usize = 8;
-            let pp = Params::<K>::new();
+
+            let pp = Params::<K, C>::new();
             let mut rng = test_rng();
 
-            let (sk_a, _) = keygen(&pp, &mut rng).unwrap();
-            let (sk_b, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
 
-            let m1 = Scalar::rand(&mut rng);
-            let m2 = Scalar::rand(&mut rng);
-            let m3 = Scalar::rand(&mut rng);
+            let msg = C::Scalar::rand(&mut rng);
+            let wrong_msg = C::Scalar::rand(&mut rng);
 
-            let lab1 = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
-            let lab2 = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
-            let lab3 = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let share = sign(&pp, &sk, label, msg).unwrap();
 
-            let sh1 = sign(&pp, &sk_a, lab1, m1).unwrap();
-            let sh2 = sign(&pp, &sk_b, lab2, m2).unwrap();
-            let sh3 = sign(&pp, &sk_a, lab3, m3).unwrap();
+            let program = LabeledProgram::new(vec![C::Scalar::from(1u64)], vec![label]).unwrap();
 
-            let coeffs = vec![Scalar::from(2), Scalar::from(3), Scalar::from(4)];
-            let program = LabeledProgram::new(coeffs.clone(), vec![lab1, lab2, lab3]).unwrap();
-            let aggr = eval(&pp, &program, vec![sh1.clone(), sh2.clone(), sh3.clone()]).unwrap();
+            let aggr = eval(&pp, &program, vec![share]).unwrap();
 
-            // ord_ids: A first, then B
-            assert_eq!(aggr.ord_ids().len(), 2);
-            assert_eq!(aggr.ord_ids()[0], sk_a.id());
-            assert_eq!(aggr.ord_ids()[1], sk_b.id());
+            let mut pks = HashMap::new();
+            pks.insert(pk.id(), pk);
 
-            // mu_A = 2*m1 + 4*m3
-            let expected_mu_a = Scalar::from(2) * m1 + Scalar::from(4) * m3;
-            assert_eq!(aggr.mus()[0], expected_mu_a);
+            let ok = verify(&pp, &program, &pks, wrong_msg, &aggr).unwrap();
+            assert!(!ok);
+        }
 
-            // mu_B = 3*m2
-            let expected_mu_b = Scalar::from(3) * m2;
-            assert_eq!(aggr.mus()[1], expected_mu_b);
+        #[test]
+        fn fails_on_wrong_message() {
+            on_each_backend!(fails_on_wrong_message_on);
+        }
 
-            // gamma = 2*γ1 + 3*γ2 + 4*γ3
-            let expected_gamma = *sh1.gamma() * Scalar::from(2)
-                + *sh2.gamma() * Scalar::from(3)
-                + *sh3.gamma() * Scalar::from(4);
-            assert_eq!(*aggr.gamma(), expected_gamma);
+        fn missing_public_key_errors_on<C: Curve>() {
+            const K: usize = 8;
+
+            let pp = Params::<K, C>::new();
+            let mut rng = test_rng();
+
+            let (sk, _pk) = keygen(&pp, &mut rng).unwrap();
+
+            let msg = C::Scalar::rand(&mut rng);
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+
+            let share = sign(&pp, &sk, label, msg).unwrap();
+
+            let program = LabeledProgram::new(vec![C::Scalar::from(1u64)], vec![label]).unwrap();
+
+            let aggr = eval(&pp, &program, vec![share]).unwrap();
+
+            let pks = HashMap::new(); // empty!
+
+            assert!(verify(&pp, &program, &pks, msg, &aggr).is_err());
+        }
+
+        #[test]
+        fn missing_public_key_errors() {
+            on_each_backend!(missing_public_key_errors_on);
+        }
+
+        fn fails_if_gamma_tampered_on<C: Curve>() {
+            const K: usize = 8;
+
+            let pp = Params::<K, C>::new();
+            let mut rng = test_rng();
+
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+
+            let msg = C::Scalar::rand(&mut rng);
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+
+            let share = sign(&pp, &sk, label, msg).unwrap();
+
+            let program = LabeledProgram::new(vec![C::Scalar::from(1u64)], vec![label]).unwrap();
+
+            let mut aggr = eval(&pp, &program, vec![share]).unwrap();
+
+            // tamper gamma
+            *aggr.gamma_mut() += C::g1_gen();
+
+            let mut pks = HashMap::new();
+            pks.insert(pk.id(), pk);
+
+            let ok = verify(&pp, &program, &pks, msg, &aggr).unwrap();
+            assert!(!ok);
+        }
+
+        #[test]
+        fn fails_if_gamma_tampered() {
+            on_each_backend!(fails_if_gamma_tampered_on);
+        }
+
+        fn fails_if_mu_tampered_on<C: Curve>() {
+            const K: usize = 8;
+
+            let pp = Params::<K, C>::new();
+            let mut rng = test_rng();
+
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+
+            let msg = C::Scalar::rand(&mut rng);
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+
+            let share = sign(&pp, &sk, label, msg).unwrap();
+
+            let program = LabeledProgram::new(vec![C::Scalar::from(1u64)], vec![label]).unwrap();
+
+            let mut aggr = eval(&pp, &program, vec![share]).unwrap();
+
+            // tamper mu
+            aggr.mus_mut()[0] += C::Scalar::from(1u64);
+
+            let mut pks = HashMap::new();
+            pks.insert(pk.id(), pk);
+
+            let ok = verify(&pp, &program, &pks, msg, &aggr).unwrap();
+            assert!(!ok);
         }
 
         #[test]
-        fn zero_coefficients() {
-            // coeff=0 should contribute nothing
+        fn fails_if_mu_tampered() {
+            on_each_backend!(fails_if_mu_tampered_on);
+        }
+
+        fn two_users_on<C: Curve>() {
             const K: usize = 8;
-            let pp = Params::<K>::new();
+
+            let pp = Params::<K, C>::new();
             let mut rng = test_rng();
 
-            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
 
-            let m1 = Scalar::rand(&mut rng);
-            let m2 = Scalar::rand(&mut rng);
+            let msg_a = C::Scalar::rand(&mut rng);
+            let msg_b = C::Scalar::rand(&mut rng);
 
-            let lab1 = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
-            let lab2 = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let lab_a = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
+            let lab_b = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
 
-            let sh1 = sign(&pp, &sk, lab1, m1).unwrap();
-            let sh2 = sign(&pp, &sk, lab2, m2).unwrap();
+            let sh_a = sign(&pp, &sk_a, lab_a, msg_a).unwrap();
+            let sh_b = sign(&pp, &sk_b, lab_b, msg_b).unwrap();
 
-            let coeffs = vec![Scalar::from(7), Scalar::zero()];
-            let program = LabeledProgram::new(coeffs, vec![lab1, lab2]).unwrap();
-            let aggr = eval(&pp, &program, vec![sh1.clone(), sh2]).unwrap();
+            let coeffs = vec![C::Scalar::from(1u64), C::Scalar::from(1u64)];
+            let program = LabeledProgram::new(coeffs, vec![lab_a, lab_b]).unwrap();
 
-            // mu should only reflect m1
-            assert_eq!(aggr.mus()[0], Scalar::from(7) * m1);
+            let aggr = eval(&pp, &program, vec![sh_a, sh_b]).unwrap();
 
-            // gamma should only reflect sh1
-            assert_eq!(*aggr.gamma(), *sh1.gamma() * Scalar::from(7));
+            let mut pks = HashMap::new();
+            pks.insert(pk_a.id(), pk_a);
+            pks.insert(pk_b.id(), pk_b);
+
+            let expected_msg = msg_a + msg_b;
+
+            let ok = verify(&pp, &program, &pks, expected_msg, &aggr).unwrap();
+            assert!(ok);
         }
 
         #[test]
-        fn length_mismatch_error() {
-            const K: usize = 8;
-            let pp = Params::<K>::new();
-            let mut rng = test_rng();
+        fn two_users() {
+            on_each_backend!(two_users_on);
+        }
+    }
 
-            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+    mod program_size_cap_tests {
 
-            let lab = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
-            let sh = sign(&pp, &sk, lab, Scalar::from(42)).unwrap();
+        use super::*;
 
-            // 2 coeffs but 1 label
-            assert!(
-                LabeledProgram::new(vec![Scalar::from(1), Scalar::from(2)], vec![lab],).is_err()
-            );
+        #[test]
+        fn oversized_program_is_rejected() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk.id(), pk)].into();
 
-            // 1 coeff, 1 label, but 2 shares
-            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![lab]).unwrap();
-            assert!(eval(&pp, &program, vec![sh.clone(), sh]).is_err());
+            let msgs = [0u64, 1, 2].map(Scalar::from);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk; 3], &msgs);
+            let program = LabeledProgram::new(vec![Scalar::from(1); 3], labels).unwrap();
+            let aggr = eval(&pp, &program, shares.clone()).unwrap();
+            let msg = Scalar::from(3);
+
+            let roomy = Params::<K>::new().with_max_program_terms(3);
+            assert!(eval(&roomy, &program, shares.clone()).is_ok());
+            assert!(verify(&roomy, &program, &pks, msg, &aggr).unwrap());
+
+            let tight = Params::<K>::new().with_max_program_terms(2);
+            let too_large = |r: Result<_, ProtocolError>| matches!(r, Err(ProtocolError::InvalidInput(m)) if m == "program too large");
+            assert!(too_large(eval(&tight, &program, shares).map(|_| ())));
+            assert!(too_large(
+                verify(&tight, &program, &pks, msg, &aggr).map(|_| ())
+            ));
+            assert!(too_large(
+                verify_result(&tight, &program, &pks, &aggr, msg).map(|_| ())
+            ));
         }
     }
-    mod verify_tests {
+
+    mod pairing_cost_tests {
 
         use super::*;
+        use crate::algebra::PAIRING_COUNT;
 
         #[test]
-        fn smoke() {
+        fn matches_pairings_performed_by_verify() {
             const K: usize = 8;
-
             let pp = Params::<K>::new();
             let mut rng = test_rng();
 
-            // one signer
-            let (sk, pk) = keygen(&pp, &mut rng).expect("keygen failed");
+            let keys: Vec<_> = (0..3).map(|_| keygen(&pp, &mut rng).unwrap()).collect();
 
-            let msg = Scalar::rand(&mut rng);
-            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            // 5 terms over 3 signers
+            let signers = [0, 1, 0, 2, 1].map(|idx| &keys[idx].0);
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let msg: Scalar = msgs.iter().sum();
+            let program = LabeledProgram::new(vec![Scalar::from(1); 5], labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
 
-            let share = sign(&pp, &sk, label, msg).expect("sign failed");
+            let pks: HashMap<_, _> = keys.iter().map(|(_, pk)| (pk.id(), pk.clone())).collect();
 
-            // trivial linear program: f = 1
-            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label])
-                .expect("program build failed");
+            assert_eq!(pairing_cost(&program), 4);
 
-            let aggr = eval(&pp, &program, vec![share]).expect("eval failed");
+            PAIRING_COUNT.with(|c| c.set(0));
+            assert!(verify(&pp, &program, &pks, msg, &aggr).unwrap());
+            assert_eq!(PAIRING_COUNT.with(|c| c.get()), pairing_cost(&program));
+        }
+    }
 
-            let mut pks = HashMap::new();
-            pks.insert(pk.id(), pk);
+    mod batch_verify_tests {
 
-            let result = verify(&pp, &program, &pks, msg, &aggr).expect("verify errored");
+        use super::*;
 
-            assert!(result);
-        }
+        const K: usize = 8;
 
-        #[test]
-        fn fails_on_wrong_message() {
-            const K: usize = 8;
+        type Batch = (
+            Params<K>,
+            HashMap<Id<K>, PublicKey<K>>,
+            Vec<(LabeledProgram<K>, Scalar, SignAggr<K>)>,
+        );
 
+        /// Three aggregates over overlapping subsets of three signers.
+        fn batch() -> Batch {
             let pp = Params::<K>::new();
             let mut rng = test_rng();
 
-            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let keys: Vec<_> = (0..3).map(|_| keygen(&pp, &mut rng).unwrap()).collect();
+            let pks = keys.iter().map(|(_, pk)| (pk.id(), pk.clone())).collect();
 
-            let msg = Scalar::rand(&mut rng);
-            let wrong_msg = Scalar::rand(&mut rng);
+            let layouts: [&[usize]; 3] = [&[0, 1], &[1, 2, 1], &[2]];
+            let items = layouts
+                .iter()
+                .map(|layout| {
+                    let signers: Vec<_> = layout.iter().map(|&s| &keys[s].0).collect();
+                    let coeffs = rand_scalars(&mut rng, signers.len());
+                    let msgs = rand_scalars(&mut rng, signers.len());
+                    let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+                    let msg: Scalar = coeffs.iter().zip(&msgs).map(|(f, m)| *f * m).sum();
+                    let program = LabeledProgram::new(coeffs, labels).unwrap();
+                    let aggr = eval(&pp, &program, shares).unwrap();
+                    (program, msg, aggr)
+                })
+                .collect();
+            (pp, pks, items)
+        }
 
-            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
-            let share = sign(&pp, &sk, label, msg).unwrap();
+        fn as_items(raw: &[(LabeledProgram<K>, Scalar, SignAggr<K>)]) -> Vec<BatchItem<'_, K>> {
+            raw.iter()
+                .map(|(p, m, a)| BatchItem::new(p, *m, a))
+                .collect()
+        }
 
-            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label]).unwrap();
+        #[test]
+        fn valid_batch_verifies() {
+            let (pp, pks, raw) = batch();
+            let items = as_items(&raw);
 
-            let aggr = eval(&pp, &program, vec![share]).unwrap();
+            for item in &items {
+                assert!(verify(&pp, item.program, &pks, item.msg, item.sign_aggr).unwrap());
+            }
+            assert!(batch_verify(&pp, &pks, &items, &mut test_rng()).unwrap());
+            assert!(batch_verify_fs(&pp, &pks, &items).unwrap());
+            assert!(batch_verify_fs(&pp, &pks, &[]).unwrap());
+        }
 
-            let mut pks = HashMap::new();
-            pks.insert(pk.id(), pk);
+        #[test]
+        fn report_pins_failures() {
+            let (pp, pks, raw) = batch();
+            let good = as_items(&raw);
+            assert_eq!(
+                batch_verify_report(&pp, &pks, &good).unwrap(),
+                vec![true; 3]
+            );
 
-            let ok = verify(&pp, &program, &pks, wrong_msg, &aggr).unwrap();
-            assert!(!ok);
+            // right output, but a mu moved between signers: ver1 holds,
+            // the pairing check does not
+            let mut shifted = raw[1].2.clone();
+            shifted.mus_mut()[0] += Scalar::from(1);
+            shifted.mus_mut()[1] -= Scalar::from(1);
+
+            let items = vec![
+                good[0],
+                good[1],
+                BatchItem::new(&raw[1].0, raw[1].1 + Scalar::from(1), &raw[1].2),
+                good[2],
+                BatchItem::new(&raw[1].0, raw[1].1, &shifted),
+            ];
+            assert!(!batch_verify_fs(&pp, &pks, &items).unwrap());
+            assert_eq!(
+                batch_verify_report(&pp, &pks, &items).unwrap(),
+                vec![true, true, false, true, false]
+            );
+            assert!(batch_verify_report(&pp, &HashMap::new(), &items).is_err());
         }
 
         #[test]
-        fn missing_public_key_errors() {
-            const K: usize = 8;
+        fn budget_bounds_pairings() {
+            use crate::algebra::PAIRING_COUNT;
 
-            let pp = Params::<K>::new();
-            let mut rng = test_rng();
+            let (pp, pks, raw) = batch();
+            let items = as_items(&raw);
 
-            let (sk, _pk) = keygen(&pp, &mut rng).unwrap();
+            // three distinct signers plus gamma
+            assert_eq!(
+                batch_verify_with_budget(&pp, &pks, &items, 4).unwrap(),
+                Some(true)
+            );
 
-            let msg = Scalar::rand(&mut rng);
-            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            PAIRING_COUNT.with(|c| c.set(0));
+            assert_eq!(
+                batch_verify_with_budget(&pp, &pks, &items, 3).unwrap(),
+                None
+            );
+            assert_eq!(PAIRING_COUNT.with(|c| c.get()), 0);
+        }
 
-            let share = sign(&pp, &sk, label, msg).unwrap();
+        #[test]
+        fn one_tampered_item_fails_batch() {
+            let (pp, pks, mut raw) = batch();
+            *raw[1].2.gamma_mut() += g1_gen();
+            let items = as_items(&raw);
 
-            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label]).unwrap();
+            assert!(!batch_verify(&pp, &pks, &items, &mut test_rng()).unwrap());
+            assert!(!batch_verify_fs(&pp, &pks, &items).unwrap());
+        }
 
-            let aggr = eval(&pp, &program, vec![share]).unwrap();
+        #[test]
+        fn wrong_message_fails_batch() {
+            let (pp, pks, mut raw) = batch();
+            raw[2].1 += Scalar::from(1);
+            let items = as_items(&raw);
 
-            let pks = HashMap::new(); // empty!
+            assert!(!batch_verify_fs(&pp, &pks, &items).unwrap());
+        }
 
-            assert!(verify(&pp, &program, &pks, msg, &aggr).is_err());
+        #[test]
+        fn challenge_scalars_are_deterministic() {
+            let (pp, pks, raw) = batch();
+            let items = as_items(&raw);
+
+            let r1 = batch_challenge_scalars(&pp, &pks, &items).unwrap();
+            let r2 = batch_challenge_scalars(&pp, &pks, &items).unwrap();
+            assert_eq!(r1.len(), 3);
+            assert_eq!(r1, r2);
+            assert!(r1[0] != r1[1] && r1[1] != r1[2]);
         }
 
         #[test]
-        fn fails_if_gamma_tampered() {
-            const K: usize = 8;
+        fn tampering_changes_challenge_scalars() {
+            let (pp, pks, raw) = batch();
+            let base = batch_challenge_scalars(&pp, &pks, &as_items(&raw)).unwrap();
+
+            let mut gamma_tampered = raw.clone();
+            *gamma_tampered[0].2.gamma_mut() += g1_gen();
+
+            let mut mu_tampered = raw.clone();
+            mu_tampered[2].2.mus_mut()[0] += Scalar::from(1);
+
+            let mut msg_tampered = raw.clone();
+            msg_tampered[1].1 += Scalar::from(1);
+
+            let mut program_tampered = raw.clone();
+            let mut coeffs = program_tampered[1].0.coeffs().to_vec();
+            coeffs[0] += Scalar::from(1);
+            program_tampered[1].0 =
+                LabeledProgram::new(coeffs, program_tampered[1].0.labels().to_vec()).unwrap();
+
+            for tampered in [gamma_tampered, mu_tampered, msg_tampered, program_tampered] {
+                let r = batch_challenge_scalars(&pp, &pks, &as_items(&tampered)).unwrap();
+                for (a, b) in r.iter().zip(&base) {
+                    assert_ne!(a, b);
+                }
+            }
+        }
+    }
+
+    mod prepared_keys_tests {
+
+        use super::*;
 
+        const K: usize = 8;
+
+        /// Program over `n_signers` signers with two terms each.
+        fn setup(
+            n_signers: usize,
+        ) -> (
+            Params<K>,
+            LabeledProgram<K>,
+            HashMap<Id<K>, PublicKey<K>>,
+            Scalar,
+            SignAggr<K>,
+        ) {
             let pp = Params::<K>::new();
             let mut rng = test_rng();
 
-            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let keys: Vec<_> = (0..n_signers)
+                .map(|_| keygen(&pp, &mut rng).unwrap())
+                .collect();
+            let signers: Vec<_> = keys.iter().chain(&keys).map(|(sk, _)| sk).collect();
+            let coeffs = rand_scalars(&mut rng, signers.len());
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let msg: Scalar = coeffs.iter().zip(&msgs).map(|(f, m)| *f * m).sum();
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+            let pks = keys.into_iter().map(|(_, pk)| (pk.id(), pk)).collect();
+            (pp, program, pks, msg, aggr)
+        }
 
-            let msg = Scalar::rand(&mut rng);
-            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+        fn prepare_all(pks: &HashMap<Id<K>, PublicKey<K>>) -> HashMap<Id<K>, PreparedPublicKey<K>> {
+            pks.iter().map(|(id, pk)| (*id, pk.prepare())).collect()
+        }
 
-            let share = sign(&pp, &sk, label, msg).unwrap();
+        #[test]
+        fn agrees_with_raw_keys() {
+            let (pp, program, pks, msg, mut aggr) = setup(3);
+            let prepared = prepare_all(&pks);
 
-            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label]).unwrap();
+            assert!(verify(&pp, &program, &pks, msg, &aggr).unwrap());
+            assert!(verify_prepared_keys(&pp, &program, &prepared, msg, &aggr).unwrap());
 
-            let mut aggr = eval(&pp, &program, vec![share]).unwrap();
+            let wrong = msg + Scalar::from(1);
+            assert!(!verify(&pp, &program, &pks, wrong, &aggr).unwrap());
+            assert!(!verify_prepared_keys(&pp, &program, &prepared, wrong, &aggr).unwrap());
 
-            // tamper gamma
             *aggr.gamma_mut() += g1_gen();
+            assert!(!verify(&pp, &program, &pks, msg, &aggr).unwrap());
+            assert!(!verify_prepared_keys(&pp, &program, &prepared, msg, &aggr).unwrap());
+        }
 
-            let mut pks = HashMap::new();
-            pks.insert(pk.id(), pk);
+        #[test]
+        fn missing_prepared_key_errors() {
+            let (pp, program, pks, msg, aggr) = setup(2);
+            let mut prepared = prepare_all(&pks);
+            let id = aggr.ord_ids()[0];
+            prepared.remove(&id);
 
-            let ok = verify(&pp, &program, &pks, msg, &aggr).unwrap();
-            assert!(!ok);
+            assert!(verify_prepared_keys(&pp, &program, &prepared, msg, &aggr).is_err());
         }
 
+        /// Timing comparison; run with `cargo test --release -- --ignored --nocapture`.
         #[test]
-        fn fails_if_mu_tampered() {
+        #[ignore]
+        fn bench_prepared_vs_raw() {
+            use std::time::Instant;
+
+            let (pp, program, pks, msg, aggr) = setup(16);
+            let prepared = prepare_all(&pks);
+            let rounds = 10;
+
+            let t = Instant::now();
+            for _ in 0..rounds {
+                assert!(verify(&pp, &program, &pks, msg, &aggr).unwrap());
+            }
+            let raw = t.elapsed();
+
+            let t = Instant::now();
+            for _ in 0..rounds {
+                assert!(verify_prepared_keys(&pp, &program, &prepared, msg, &aggr).unwrap());
+            }
+            let prep = t.elapsed();
+
+            println!(
+                "16 signers x {rounds}: raw {raw:?}, prepared {prep:?} ({:.2}x)",
+                raw.as_secs_f64() / prep.as_secs_f64()
+            );
+        }
+    }
+
+    mod prepared_program_tests {
+
+        use super::*;
+
+        #[test]
+        fn agrees_with_unprepared() {
             const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a), (pk_b.id(), pk_b)].into();
+
+            let signers = [&sk_a, &sk_b, &sk_a, &sk_b];
+            let coeffs = vec![
+                Scalar::from(2),
+                Scalar::zero(),
+                Scalar::from(7),
+                Scalar::from(4),
+            ];
+            let msgs = rand_scalars(&mut rng, signers.len());
+            let (labels, shares) = sign_terms(&pp, &mut rng, &signers, &msgs);
+            let msg: Scalar = coeffs.iter().zip(&msgs).map(|(f, m)| *f * m).sum();
+            let program = LabeledProgram::new(coeffs, labels).unwrap();
+            let mut aggr = eval(&pp, &program, shares).unwrap();
+            let prepared = program.prepare(&pp).unwrap();
+            assert_eq!(prepared.label_points().len(), program.n());
+
+            for m in [msg, msg + Scalar::from(1)] {
+                assert_eq!(
+                    verify_prepared(&pp, &prepared, &pks, m, &aggr).unwrap(),
+                    verify(&pp, &program, &pks, m, &aggr).unwrap()
+                );
+            }
+            assert!(verify_prepared(&pp, &prepared, &pks, msg, &aggr).unwrap());
 
+            *aggr.gamma_mut() += g1_gen();
+            assert!(!verify_prepared(&pp, &prepared, &pks, msg, &aggr).unwrap());
+            assert!(!verify(&pp, &program, &pks, msg, &aggr).unwrap());
+        }
+
+        #[test]
+        fn bytes_round_trip_verifies() {
+            const K: usize = 8;
             let pp = Params::<K>::new();
             let mut rng = test_rng();
 
             let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let pks = HashMap::from([(pk.id(), pk)]);
+            let msgs = [3u64, 8].map(Scalar::from);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk; 2], &msgs);
+            let program = LabeledProgram::from_u64_coeffs(vec![5, 2], labels).unwrap();
+            let aggr = eval(&pp, &program, shares).unwrap();
+
+            let bytes = program.prepare(&pp).unwrap().to_bytes();
+            assert_eq!(bytes.len(), 32 + 4 + 2 * (32 + 2 * K + 48));
+            let loaded = PreparedProgram::<K>::from_bytes(&bytes).unwrap();
+            assert_eq!(loaded.to_bytes(), bytes);
+            assert_eq!(loaded.program().fingerprint(), program.fingerprint());
+            assert_eq!(*loaded.dst_fingerprint(), pp.dst_fingerprint());
+
+            // 15 + 16
+            for m in [31u64, 32] {
+                assert_eq!(
+                    verify_prepared(&pp, &loaded, &pks, Scalar::from(m), &aggr).unwrap(),
+                    verify(&pp, &program, &pks, Scalar::from(m), &aggr).unwrap()
+                );
+            }
+            assert!(verify_prepared(&pp, &loaded, &pks, Scalar::from(31), &aggr).unwrap());
 
-            let msg = Scalar::rand(&mut rng);
-            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            // loaded under other params it is refused
+            let other = pp.with_new_dst(b"OTHER-DST").unwrap();
+            assert!(verify_prepared(&other, &loaded, &pks, Scalar::from(31), &aggr).is_err());
 
-            let share = sign(&pp, &sk, label, msg).unwrap();
+            assert!(PreparedProgram::<K>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+            let mut bad = bytes.clone();
+            bad[32 + 4 + 32 + 2 * K] ^= 0x01;
+            assert!(PreparedProgram::<K>::from_bytes(&bad).is_err());
+        }
+    }
 
-            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label]).unwrap();
+    mod verify_terms_tests {
 
-            let mut aggr = eval(&pp, &program, vec![share]).unwrap();
+        use super::*;
 
-            // tamper mu
-            aggr.mus_mut()[0] += Scalar::from(1);
+        #[test]
+        fn sides_equal_iff_valid() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
 
-            let mut pks = HashMap::new();
-            pks.insert(pk.id(), pk);
+            let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+            let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+            let pks: HashMap<_, _> = [(pk_a.id(), pk_a), (pk_b.id(), pk_b)].into();
 
-            let ok = verify(&pp, &program, &pks, msg, &aggr).unwrap();
-            assert!(!ok);
+            let msgs = rand_scalars(&mut rng, 3);
+            let (labels, shares) = sign_terms(&pp, &mut rng, &[&sk_a, &sk_b, &sk_a], &msgs);
+            let msg: Scalar = msgs.iter().sum();
+            let program = LabeledProgram::new(vec![Scalar::from(1); 3], labels).unwrap();
+            let mut aggr = eval(&pp, &program, shares).unwrap();
+
+            let (lhs, rhs) = verify_terms(&pp, &program, &pks, &aggr).unwrap();
+            assert_eq!(lhs, rhs);
+            assert!(verify(&pp, &program, &pks, msg, &aggr).unwrap());
+
+            *aggr.gamma_mut() += g1_gen();
+            let (lhs, rhs) = verify_terms(&pp, &program, &pks, &aggr).unwrap();
+            assert_ne!(lhs, rhs);
+            assert!(!verify(&pp, &program, &pks, msg, &aggr).unwrap());
         }
+    }
+
+    mod verify_result_tests {
+
+        use super::*;
 
         #[test]
-        fn two_users() {
+        fn accepts_correct_and_rejects_wrong_output() {
             const K: usize = 8;
-
             let pp = Params::<K>::new();
             let mut rng = test_rng();
 
             let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
             let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
 
-            let msg_a = Scalar::rand(&mut rng);
-            let msg_b = Scalar::rand(&mut rng);
-
+            let m_a = Scalar::rand(&mut rng);
+            let m_b = Scalar::rand(&mut rng);
             let lab_a = Label::new(sk_a.id(), rand_tag::<K, _>(&mut rng));
             let lab_b = Label::new(sk_b.id(), rand_tag::<K, _>(&mut rng));
 
-            let sh_a = sign(&pp, &sk_a, lab_a, msg_a).unwrap();
-            let sh_b = sign(&pp, &sk_b, lab_b, msg_b).unwrap();
+            let sh_a = sign(&pp, &sk_a, lab_a, m_a).unwrap();
+            let sh_b = sign(&pp, &sk_b, lab_b, m_b).unwrap();
 
-            let coeffs = vec![Scalar::from(1), Scalar::from(1)];
+            let coeffs = vec![Scalar::from(2), Scalar::from(9)];
             let program = LabeledProgram::new(coeffs, vec![lab_a, lab_b]).unwrap();
-
             let aggr = eval(&pp, &program, vec![sh_a, sh_b]).unwrap();
 
             let mut pks = HashMap::new();
             pks.insert(pk_a.id(), pk_a);
             pks.insert(pk_b.id(), pk_b);
 
-            let expected_msg = msg_a + msg_b;
+            let y = Scalar::from(2) * m_a + Scalar::from(9) * m_b;
+            assert!(verify_result(&pp, &program, &pks, &aggr, y).unwrap());
+            assert!(!verify_result(&pp, &program, &pks, &aggr, y + Scalar::from(1)).unwrap());
+        }
 
-            let ok = verify(&pp, &program, &pks, expected_msg, &aggr).unwrap();
-            assert!(ok);
+        #[test]
+        fn rejects_tampered_gamma_even_with_correct_output() {
+            const K: usize = 8;
+            let pp = Params::<K>::new();
+            let mut rng = test_rng();
+
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let msg = Scalar::rand(&mut rng);
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let share = sign(&pp, &sk, label, msg).unwrap();
+
+            let program = LabeledProgram::new(vec![Scalar::from(1)], vec![label]).unwrap();
+            let mut aggr = eval(&pp, &program, vec![share]).unwrap();
+            *aggr.gamma_mut() += g1_gen();
+
+            let mut pks = HashMap::new();
+            pks.insert(pk.id(), pk);
+
+            assert!(!verify_result(&pp, &program, &pks, &aggr, msg).unwrap());
+        }
+    }
+
+    mod verify_dyn_tests {
+
+        use super::*;
+        use crate::types::DynInputs;
+
+        /// One-signer program with its keys, aggregate and output.
+        fn setup<const K: usize>(
+            pp: &Params<K>,
+        ) -> (
+            LabeledProgram<K>,
+            HashMap<Id<K>, PublicKey<K>>,
+            SignAggr<K>,
+            Scalar,
+        ) {
+            let mut rng = test_rng();
+            let (sk, pk) = keygen(pp, &mut rng).unwrap();
+            let label = Label::new(sk.id(), rand_tag::<K, _>(&mut rng));
+            let msg = Scalar::rand(&mut rng);
+            let program = LabeledProgram::new(vec![Scalar::from(2)], vec![label]).unwrap();
+            let share = sign(pp, &sk, label, msg).unwrap();
+            let aggr = eval(pp, &program, vec![share]).unwrap();
+            (
+                program,
+                HashMap::from([(pk.id(), pk)]),
+                aggr,
+                msg * Scalar::from(2),
+            )
+        }
+
+        #[test]
+        fn dispatches_on_k() {
+            let (pp16, pp32) = (Params::<16>::new(), Params::<32>::new());
+            let (prog16, pks16, aggr16, y16) = setup(&pp16);
+            let (prog32, pks32, aggr32, y32) = setup(&pp32);
+
+            let verifier = DynVerifier {
+                k16: Some(DynInputs::new(&pp16, &prog16, &pks16)),
+                k32: Some(DynInputs::new(&pp32, &prog32, &pks32)),
+            };
+
+            let bytes16 = DynAggr::from(aggr16).to_bytes();
+            let bytes32 = DynAggr::from(aggr32.clone()).to_bytes();
+            assert_eq!(DynAggr::from_bytes(&bytes32).unwrap().k(), 32);
+            assert!(verify_dyn(&bytes16, y16, &verifier).unwrap());
+            assert!(verify_dyn(&bytes32, y32, &verifier).unwrap());
+            assert!(!verify_dyn(&bytes32, y16, &verifier).unwrap());
+
+            let only16 = DynVerifier {
+                k16: verifier.k16,
+                ..Default::default()
+            };
+            assert!(verify_dyn(&bytes32, y32, &only16).is_err());
+        }
+
+        #[test]
+        fn unsupported_k_is_rejected() {
+            let (_, _, aggr, _) = setup(&Params::<8>::new());
+            let mut bytes = vec![8u8];
+            bytes.extend_from_slice(&aggr.to_bytes());
+            assert!(DynAggr::from_bytes(&bytes).is_err());
+            assert!(DynAggr::from_bytes(&[]).is_err());
         }
     }
 }
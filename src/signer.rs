@@ -0,0 +1,210 @@
+//! Signer-side convenience wrapper around a secret key and its `Params`.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use crate::{
+    algebra::Scalar,
+    encoding::{BigEndianInteger, MessageEncoder},
+    errors::ProtocolError,
+    params::Params,
+    protocol::sign,
+    types::{Id, Label, SecretKey, SignShare},
+};
+
+/// A secret key bound to the `Params` it signs under, so neither has to be
+/// threaded through every `sign` call. The encoder maps raw data for
+/// [`Signer::sign_raw`] and defaults to [`BigEndianInteger`].
+pub struct Signer<'a, const K: usize, E = BigEndianInteger> {
+    pp: &'a Params<K>,
+    sk: SecretKey<K>,
+    encoder: E,
+    /// Message signed under each label so far, if tracking is enabled.
+    used: Option<Mutex<HashMap<Label<K>, Scalar>>>,
+}
+
+impl<'a, const K: usize> Signer<'a, K> {
+    pub fn new(pp: &'a Params<K>, sk: SecretKey<K>) -> Self {
+        Self {
+            pp,
+            sk,
+            encoder: BigEndianInteger,
+            used: None,
+        }
+    }
+}
+
+impl<'a, const K: usize, E: MessageEncoder> Signer<'a, K, E> {
+    /// Replaces the encoder used by [`Signer::sign_raw`].
+    pub fn with_encoder<F: MessageEncoder>(self, encoder: F) -> Signer<'a, K, F> {
+        Signer {
+            pp: self.pp,
+            sk: self.sk,
+            encoder,
+            used: self.used,
+        }
+    }
+
+    /// Remembers every label signed from now on and refuses to sign a
+    /// different message under one again: two messages under one label
+    /// break the scheme's unforgeability. Re-signing the same message is
+    /// allowed, since signing is deterministic and yields the same share.
+    pub fn with_label_tracking(mut self) -> Self {
+        self.used = Some(Mutex::new(HashMap::new()));
+        self
+    }
+
+    pub fn encoder(&self) -> &E {
+        &self.encoder
+    }
+
+    pub fn id(&self) -> Id<K> {
+        self.sk.id()
+    }
+
+    pub fn params(&self) -> &'a Params<K> {
+        self.pp
+    }
+
+    /// Signs `msg` under `label`, which must carry this signer's id. With
+    /// [`Signer::with_label_tracking`], errors with `"label reused"` if
+    /// `label` was already signed with a different message.
+    pub fn sign(&self, label: Label<K>, msg: Scalar) -> Result<SignShare<K>, ProtocolError> {
+        let Some(used) = &self.used else {
+            return sign(self.pp, &self.sk, label, msg);
+        };
+        // a panic elsewhere cannot leave the map half-updated
+        let mut used = used.lock().unwrap_or_else(|e| e.into_inner());
+        match used.get(&label) {
+            Some(prev) if *prev != msg => {
+                Err(ProtocolError::InvalidInput("label reused".to_string()))
+            }
+            Some(_) => sign(self.pp, &self.sk, label, msg),
+            None => {
+                let share = sign(self.pp, &self.sk, label, msg)?;
+                used.insert(label, msg);
+                Ok(share)
+            }
+        }
+    }
+
+    /// Encodes `raw` with this signer's encoder and signs the result.
+    pub fn sign_raw(&self, label: Label<K>, raw: &[u8]) -> Result<SignShare<K>, ProtocolError> {
+        self.sign(label, self.encoder.encode(raw)?)
+    }
+
+    /// Signs every `(label, msg)` pair in order, failing on the first label
+    /// that does not carry this signer's id.
+    pub fn sign_batch(
+        &self,
+        items: &[(Label<K>, Scalar)],
+    ) -> Result<Vec<SignShare<K>>, ProtocolError> {
+        items
+            .iter()
+            .map(|(label, msg)| self.sign(*label, *msg))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{encoding::FixedPoint, protocol::keygen, types::Tag};
+    use ark_std::{UniformRand, test_rng};
+
+    const K: usize = 8;
+
+    #[test]
+    fn matches_free_sign() {
+        let pp = Params::<K>::new();
+        let mut rng = test_rng();
+        let (sk, _pk) = keygen(&pp, &mut rng).unwrap();
+
+        let label = Label::new(sk.id(), Tag([3u8; K]));
+        let msg = Scalar::rand(&mut rng);
+
+        let expected = sign(&pp, &sk, label, msg).unwrap();
+        let signer = Signer::new(&pp, sk);
+        assert_eq!(signer.sign(label, msg).unwrap(), expected);
+    }
+
+    #[test]
+    fn sign_batch_matches_individual_signs() {
+        let pp = Params::<K>::new();
+        let mut rng = test_rng();
+        let (sk, _pk) = keygen(&pp, &mut rng).unwrap();
+        let signer = Signer::new(&pp, sk);
+
+        let items: Vec<(Label<K>, Scalar)> = (0u8..4)
+            .map(|i| (Label::new(signer.id(), Tag([i; K])), Scalar::rand(&mut rng)))
+            .collect();
+
+        let batch = signer.sign_batch(&items).unwrap();
+        assert_eq!(batch.len(), 4);
+        for ((label, msg), share) in items.iter().zip(&batch) {
+            assert_eq!(*share, signer.sign(*label, *msg).unwrap());
+        }
+    }
+
+    #[test]
+    fn sign_raw_uses_encoder() {
+        let pp = Params::<K>::new();
+        let (sk, _) = keygen(&pp, &mut test_rng()).unwrap();
+        let label = Label::new(sk.id(), Tag([1u8; K]));
+
+        let signer = Signer::new(&pp, sk);
+        assert_eq!(
+            signer.sign_raw(label, &300u16.to_be_bytes()).unwrap(),
+            signer.sign(label, Scalar::from(300u64)).unwrap()
+        );
+
+        let signer = signer.with_encoder(FixedPoint::new(1));
+        assert_eq!(
+            signer.sign_raw(label, b"30.0").unwrap(),
+            signer.sign(label, Scalar::from(300u64)).unwrap()
+        );
+        assert!(signer.sign_raw(label, b"30.05").is_err());
+    }
+
+    #[test]
+    fn tracking_rejects_label_reuse() {
+        let pp = Params::<K>::new();
+        let (sk, _) = keygen(&pp, &mut test_rng()).unwrap();
+        let label = Label::new(sk.id(), Tag([7u8; K]));
+        let other = Label::new(sk.id(), Tag([8u8; K]));
+
+        let untracked = Signer::new(&pp, sk.clone());
+        untracked.sign(label, Scalar::from(1)).unwrap();
+        assert!(untracked.sign(label, Scalar::from(2)).is_ok());
+
+        let signer = Signer::new(&pp, sk).with_label_tracking();
+        let first = signer.sign(label, Scalar::from(1)).unwrap();
+        assert_eq!(signer.sign(label, Scalar::from(1)).unwrap(), first);
+        assert!(matches!(
+            signer.sign(label, Scalar::from(2)),
+            Err(ProtocolError::InvalidInput(msg)) if msg == "label reused"
+        ));
+        signer.sign(other, Scalar::from(2)).unwrap();
+
+        // tracking survives swapping the encoder
+        let signer = signer.with_encoder(FixedPoint::new(0));
+        assert!(signer.sign_raw(other, b"3").is_err());
+        assert!(signer.sign_raw(other, b"2").is_ok());
+    }
+
+    #[test]
+    fn foreign_label_is_rejected() {
+        let pp = Params::<K>::new();
+        let mut rng = test_rng();
+        let (sk, _) = keygen(&pp, &mut rng).unwrap();
+        let (other, _) = keygen(&pp, &mut rng).unwrap();
+        let signer = Signer::new(&pp, sk);
+
+        let foreign = Label::new(other.id(), Tag([0u8; K]));
+        assert!(signer.sign(foreign, Scalar::from(1)).is_err());
+
+        let own = Label::new(signer.id(), Tag([0u8; K]));
+        let items = [(own, Scalar::from(1)), (foreign, Scalar::from(2))];
+        assert!(signer.sign_batch(&items).is_err());
+    }
+}
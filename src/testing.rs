@@ -0,0 +1,96 @@
+//! Property checks for downstream test suites, enabled by the `test-utils`
+//! feature.
+
+use std::collections::HashMap;
+
+use ark_std::{
+    UniformRand,
+    rand::{Rng, RngCore, SeedableRng},
+};
+use rand_chacha::ChaCha20Rng;
+
+use crate::{
+    algebra::Scalar,
+    params::Params,
+    protocol::{eval, keygen, sign, verify},
+    types::{Label, LabeledProgram, Tag},
+};
+
+/// ChaCha20 RNG seeded from `seed`, so benchmarks and tests across the
+/// public API draw the same randomness on every run and platform.
+pub fn seeded_rng(seed: u64) -> impl RngCore {
+    ChaCha20Rng::seed_from_u64(seed)
+}
+
+/// Runs `iterations` random `sign` → `eval` → `verify` rounds and panics if
+/// any aggregate fails to verify against the program output.
+///
+/// Each round draws 1–4 signers, 1–3 labels per signer, random messages and
+/// random coefficients, and then checks that the correct output verifies and
+/// that an output off by one is rejected.
+pub fn assert_homomorphic<const K: usize, R: RngCore>(
+    pp: &Params<K>,
+    rng: &mut R,
+    iterations: usize,
+) {
+    for round in 0..iterations {
+        let n_signers = rng.gen_range(1..=4);
+
+        let mut pks = HashMap::new();
+        let mut coeffs = Vec::new();
+        let mut labels = Vec::new();
+        let mut shares = Vec::new();
+        let mut output = Scalar::from(0u64);
+        for _ in 0..n_signers {
+            let (sk, pk) = keygen(pp, rng).expect("keygen");
+            for _ in 0..rng.gen_range(1..=3) {
+                let mut tag = [0u8; K];
+                rng.fill_bytes(&mut tag);
+                let label = Label::new(sk.id(), Tag(tag));
+                let msg = Scalar::rand(rng);
+                let coeff = Scalar::rand(rng);
+
+                shares.push(sign(pp, &sk, label, msg).expect("sign"));
+                coeffs.push(coeff);
+                labels.push(label);
+                output += coeff * msg;
+            }
+            pks.insert(pk.id(), pk);
+        }
+
+        let program = LabeledProgram::new(coeffs, labels).expect("program");
+        let aggr = eval(pp, &program, shares).expect("eval");
+        assert!(
+            verify(pp, &program, &pks, output, &aggr).expect("verify"),
+            "round {round}: correct output rejected"
+        );
+        assert!(
+            !verify(pp, &program, &pks, output + Scalar::from(1u64), &aggr).expect("verify"),
+            "round {round}: wrong output accepted"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_std::test_rng;
+
+    use super::*;
+
+    #[test]
+    fn random_programs_are_homomorphic() {
+        assert_homomorphic(&Params::<8>::new(), &mut test_rng(), 8);
+    }
+
+    #[test]
+    fn seeded_rng_is_reproducible() {
+        let pp = Params::<8>::new();
+        let (sk_a, pk_a) = keygen(&pp, &mut seeded_rng(42)).unwrap();
+        let (sk_b, pk_b) = keygen(&pp, &mut seeded_rng(42)).unwrap();
+        assert_eq!(sk_a, sk_b);
+        assert_eq!(pk_a, pk_b);
+
+        let (_, pk_c) = keygen(&pp, &mut seeded_rng(43)).unwrap();
+        assert_ne!(pk_a, pk_c);
+    }
+}
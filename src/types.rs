@@ -1,19 +1,73 @@
+use std::collections::{HashMap, HashSet};
+
+use ark_ec::CurveGroup;
+use ark_ff::{Field, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::RngCore;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
 use crate::{
-    algebra::{G1, G2, Scalar},
+    algebra::{
+        Curve, DefaultCurve, G1, G1_COMPRESSED_LEN, G1Affine, G2, G2_COMPRESSED_LEN, G2Affine,
+        SCALAR_LEN, Scalar, scalar_ct_eq, scalar_from_bytes, scalar_from_i128, scalar_to_bytes,
+    },
     errors::ProtocolError,
+    params::{
+        DST_KEY_REGISTRATION, DST_LABEL_STR, DST_PROGRAM_FINGERPRINT, DST_TAG_DERIVE, Params,
+    },
+    protocol::{combine, keypair_for_id},
 };
 
 /// Identity element $\textsf{id}\in \textsf{ID}\subset \{ 0,1 \}^8\texttt{K}$
 ///
 /// Here `K` is the compile-time length in bytes, so the bit-length is `8*K`.
-#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Id<const K: usize>(pub [u8; K]);
 
+impl<const K: usize> Id<K> {
+    /// Whether every byte is zero, the usual "never assigned" sentinel.
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().all(|b| *b == 0)
+    }
+}
+
 /// Tag $\tau \in \mathcal{T} \subset \{ 0,1 \}^{8\texttt{K}}$
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
 pub struct Tag<const K: usize>(pub [u8; K]);
 
-#[derive(Clone, Debug, Copy)]
+impl<const K: usize> Tag<K> {
+    /// Deterministically derives a tag from `input` under `domain`, e.g. a
+    /// dataset name and a record's position or content.
+    ///
+    /// SHA-256 is run in counter mode over
+    /// `DST || len(domain) || domain || input || counter` and the output is
+    /// truncated to `K` bytes, so any `K` is supported.
+    pub fn derive(domain: &[u8], input: &[u8]) -> Tag<K> {
+        Tag(expand_sha256(DST_TAG_DERIVE, domain, input))
+    }
+}
+
+/// Expands `(dst, domain, input)` to `N` bytes with SHA-256 in counter mode.
+/// The domain is length-prefixed and the counter has fixed width, so distinct
+/// `(domain, input)` pairs never produce the same hash input.
+fn expand_sha256<const N: usize>(dst: &[u8], domain: &[u8], input: &[u8]) -> [u8; N] {
+    let mut out = [0u8; N];
+    for (i, chunk) in out.chunks_mut(32).enumerate() {
+        let block = Sha256::new()
+            .chain_update(dst)
+            .chain_update((domain.len() as u64).to_be_bytes())
+            .chain_update(domain)
+            .chain_update(input)
+            .chain_update((i as u32).to_be_bytes())
+            .finalize();
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    out
+}
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
 pub struct Label<const K: usize> {
     pub id: Id<K>,
     pub tag: Tag<K>,
@@ -24,6 +78,17 @@ impl<const K: usize> Label<K> {
         Self { id, tag }
     }
 
+    /// Stable label from human-readable strings, e.g. `("alice",
+    /// "record-5")`, for demos. Each string is expanded to `K` bytes with
+    /// SHA-256 in counter mode under its own domain, so the id and tag
+    /// spaces are separate. Not a substitute for real ids from `keygen`.
+    pub fn from_str_pair(id_str: &str, tag_str: &str) -> Label<K> {
+        Self::new(
+            Id(expand_sha256(DST_LABEL_STR, b"id", id_str.as_bytes())),
+            Tag(expand_sha256(DST_LABEL_STR, b"tag", tag_str.as_bytes())),
+        )
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut out = Vec::with_capacity(2 * K);
         out.extend_from_slice(&self.id.0);
@@ -38,16 +103,40 @@ impl<const K: usize> Label<K> {
     pub fn tag(&self) -> Tag<K> {
         self.tag
     }
+
+    /// Whether this label was issued to signer `id`.
+    pub fn belongs_to(&self, id: &Id<K>) -> bool {
+        self.id == *id
+    }
+
+    /// Label of component `i` of a vector record signed under this label
+    /// (see [`sign_vector`]): same id, tag `Tag::derive(tag, i as u64 BE)`,
+    /// i.e. the index appended to the tag and hashed back to `K` bytes.
+    ///
+    /// [`sign_vector`]: crate::api::sign_vector
+    pub fn component(&self, i: usize) -> Label<K> {
+        Self::new(self.id, Tag::derive(&self.tag.0, &(i as u64).to_be_bytes()))
+    }
 }
 
 #[derive(Clone, Debug)]
-pub struct SecretKey<const K: usize> {
+pub struct SecretKey<const K: usize, C: Curve = DefaultCurve> {
     id: Id<K>,
-    value: Scalar,
+    value: C::Scalar,
 }
 
-impl<const K: usize> SecretKey<K> {
-    pub const fn new(id: Id<K>, value: Scalar) -> Self {
+/// Constant time over the secret scalar (and the id), so comparing keys does
+/// not leak where two secrets first differ.
+impl<const K: usize, C: Curve> PartialEq for SecretKey<K, C> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.id.0.ct_eq(&other.id.0) & scalar_ct_eq(&self.value, &other.value)).into()
+    }
+}
+
+impl<const K: usize, C: Curve> Eq for SecretKey<K, C> {}
+
+impl<const K: usize, C: Curve> SecretKey<K, C> {
+    pub const fn new(id: Id<K>, value: C::Scalar) -> Self {
         Self { id, value }
     }
 
@@ -55,23 +144,54 @@ impl<const K: usize> SecretKey<K> {
         self.id
     }
 
-    pub const fn value(&self) -> &Scalar {
+    pub const fn value(&self) -> &C::Scalar {
         &self.value
     }
 
-    pub fn into_parts(self) -> (Id<K>, Scalar) {
+    pub fn into_parts(self) -> (Id<K>, C::Scalar) {
         (self.id, self.value)
     }
+
+    /// Key rotation: keeps this key's `id` and samples a fresh nonzero
+    /// secret scalar, returning the new keypair. Shares signed under the old
+    /// key only verify against the old public key.
+    pub fn rotate<R: RngCore>(&self, rng: &mut R) -> (SecretKey<K, C>, PublicKey<K, C>) {
+        keypair_for_id(self.id, rng)
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct PublicKey<const K: usize> {
+impl<const K: usize> SecretKey<K> {
+    /// Exports the secret scalar as its canonical 32-byte little-endian
+    /// encoding, for key backup.
+    ///
+    /// The returned array is a plain copy of the secret: callers should wipe
+    /// it (e.g. with the `zeroize` crate) as soon as it has been stored.
+    pub fn to_scalar_bytes(&self) -> [u8; SCALAR_LEN] {
+        scalar_to_bytes(&self.value)
+    }
+
+    /// Re-imports a secret exported with [`SecretKey::to_scalar_bytes`],
+    /// rejecting zero and non-canonical encodings (integers >= r).
+    pub fn from_scalar_bytes(id: Id<K>, bytes: &[u8; SCALAR_LEN]) -> Result<Self, ProtocolError> {
+        let value = scalar_from_bytes(bytes)
+            .map_err(|e| ProtocolError::InvalidInput(format!("invalid secret scalar: {e}")))?;
+        if value.is_zero() {
+            return Err(ProtocolError::InvalidInput(
+                "secret scalar must be nonzero".to_string(),
+            ));
+        }
+        Ok(Self::new(id, value))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey<const K: usize, C: Curve = DefaultCurve> {
     id: Id<K>,
-    value: G2,
+    value: C::G2,
 }
 
-impl<const K: usize> PublicKey<K> {
-    pub const fn new(id: Id<K>, value: G2) -> Self {
+impl<const K: usize, C: Curve> PublicKey<K, C> {
+    pub const fn new(id: Id<K>, value: C::G2) -> Self {
         Self { id, value }
     }
 
@@ -79,24 +199,143 @@ impl<const K: usize> PublicKey<K> {
         self.id
     }
 
-    pub const fn value(&self) -> &G2 {
+    pub const fn value(&self) -> &C::G2 {
         &self.value
     }
 
-    pub fn into_parts(self) -> (Id<K>, G2) {
+    pub fn value_affine(&self) -> <C::G2 as CurveGroup>::Affine {
+        self.value.into_affine()
+    }
+
+    pub fn into_parts(self) -> (Id<K>, C::G2) {
         (self.id, self.value)
     }
+
+    /// Precomputes the pairing data of this key for repeated verification.
+    pub fn prepare(&self) -> PreparedPublicKey<K, C> {
+        PreparedPublicKey {
+            id: self.id,
+            value: C::prepare_g2(&self.value),
+        }
+    }
+}
+
+impl<const K: usize> PublicKey<K> {
+    /// Builds a key from an affine point, e.g. one held by other arkworks
+    /// code.
+    pub fn from_affine(id: Id<K>, value: G2Affine) -> Self {
+        Self::new(id, value.into())
+    }
+
+    /// SHA-256 of `id || compressed(value)` under a dedicated DST, a short
+    /// binding handle a registration authority can record for this key.
+    pub fn registration_commitment(&self) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(DST_KEY_REGISTRATION)
+            .chain_update(self.to_bytes())
+            .finalize()
+            .into()
+    }
+
+    /// Length of the encoding produced by [`PublicKey::to_bytes`].
+    pub const SERIALIZED_LEN: usize = K + G2_COMPRESSED_LEN;
+
+    /// Encodes the key as `id || compressed(value)`, `K + 96` bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![0u8; Self::SERIALIZED_LEN];
+        self.write_to(&mut out)
+            .expect("buffer has SERIALIZED_LEN bytes");
+        out
+    }
+
+    /// Writes the [`PublicKey::to_bytes`] encoding into the start of `out`
+    /// without allocating. Stable Rust cannot name `[u8; K + 96]` as a return
+    /// type, so callers size the buffer with [`PublicKey::SERIALIZED_LEN`].
+    pub fn write_to(&self, out: &mut [u8]) -> Result<(), ProtocolError> {
+        if out.len() < Self::SERIALIZED_LEN {
+            return Err(ProtocolError::InvalidInput(format!(
+                "buffer must hold {} bytes, got {}",
+                Self::SERIALIZED_LEN,
+                out.len()
+            )));
+        }
+        let (id_out, value_out) = out[..Self::SERIALIZED_LEN].split_at_mut(K);
+        id_out.copy_from_slice(&self.id.0);
+        self.value
+            .serialize_compressed(value_out)
+            .expect("value slot has G2_COMPRESSED_LEN bytes");
+        Ok(())
+    }
+
+    /// Decodes a key produced by [`PublicKey::to_bytes`], rejecting a value
+    /// that is not on the curve or not in the prime-order subgroup.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        if bytes.len() != Self::SERIALIZED_LEN {
+            return Err(ProtocolError::InvalidInput(format!(
+                "public key must be {} bytes, got {}",
+                Self::SERIALIZED_LEN,
+                bytes.len()
+            )));
+        }
+        let (id_bytes, value_bytes) = bytes.split_at(K);
+
+        let id = Id(id_bytes.try_into().expect("length checked above"));
+        let value = G2::deserialize_compressed(value_bytes)
+            .map_err(|e| ProtocolError::InvalidInput(format!("invalid public key: {e}")))?;
+
+        Ok(Self::new(id, value))
+    }
+}
+
+/// Public key with its `G2` Miller-loop lines precomputed, built with
+/// [`PublicKey::prepare`]. Amortizes pairing cost when the same key is used
+/// across many verifications.
+#[derive(Clone, Debug)]
+pub struct PreparedPublicKey<const K: usize, C: Curve = DefaultCurve> {
+    id: Id<K>,
+    value: C::G2Prepared,
+}
+
+impl<const K: usize, C: Curve> PreparedPublicKey<K, C> {
+    pub const fn id(&self) -> Id<K> {
+        self.id
+    }
+
+    pub const fn value(&self) -> &C::G2Prepared {
+        &self.value
+    }
 }
 
 #[derive(Clone, Debug)]
-pub struct SignAggr<const K: usize> {
-    gamma: G1,
+pub struct SignAggr<const K: usize, C: Curve = DefaultCurve> {
+    gamma: C::G1,
     ord_ids: Vec<Id<K>>,
-    mus: Vec<Scalar>,
+    mus: Vec<C::Scalar>,
+    /// Per-signer share of `gamma`, aligned with `ord_ids`. Only retained by
+    /// `eval_with_breakdown`; not part of equality or the byte encoding.
+    partials: Option<Vec<C::G1>>,
+    /// Merkle root over the covered labels, set by `eval_with_commitment`;
+    /// likewise not part of equality or the byte encoding.
+    label_root: Option<[u8; 32]>,
+    /// Program the aggregate was evaluated over, set by
+    /// `eval_self_contained`; likewise not part of equality or the encoding.
+    program: Option<LabeledProgram<K, C>>,
 }
 
-impl<const K: usize> SignAggr<K> {
-    pub fn new(gamma: G1, ord_ids: Vec<Id<K>>, mus: Vec<Scalar>) -> Result<Self, ProtocolError> {
+impl<const K: usize, C: Curve> PartialEq for SignAggr<K, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.gamma == other.gamma && self.ord_ids == other.ord_ids && self.mus == other.mus
+    }
+}
+
+impl<const K: usize, C: Curve> Eq for SignAggr<K, C> {}
+
+impl<const K: usize, C: Curve> SignAggr<K, C> {
+    pub fn new(
+        gamma: C::G1,
+        ord_ids: Vec<Id<K>>,
+        mus: Vec<C::Scalar>,
+    ) -> Result<Self, ProtocolError> {
         if ord_ids.len() != mus.len() {
             return Err(ProtocolError::InvalidInput(
                 "ord_ids/mus length mismatch".to_string(),
@@ -106,10 +345,13 @@ impl<const K: usize> SignAggr<K> {
             gamma,
             ord_ids,
             mus,
+            partials: None,
+            label_root: None,
+            program: None,
         })
     }
 
-    pub const fn gamma(&self) -> &G1 {
+    pub const fn gamma(&self) -> &C::G1 {
         &self.gamma
     }
 
@@ -117,34 +359,404 @@ impl<const K: usize> SignAggr<K> {
         &self.ord_ids
     }
 
-    pub fn mus(&self) -> &[Scalar] {
+    pub fn mus(&self) -> &[C::Scalar] {
         &self.mus
     }
 
-    pub fn into_parts(self) -> (G1, Vec<Scalar>) {
-        (self.gamma, self.mus)
+    /// Number of signers in the aggregate, i.e. `ord_ids().len()`.
+    pub fn num_signers(&self) -> usize {
+        self.ord_ids.len()
+    }
+
+    /// Total output `y = sum_i f_i * m_i` of the evaluated program: the sum
+    /// of every signer's `mu`. This is the cross-signer total that [`verify`]
+    /// checks the claimed output against; per-signer parts are in
+    /// [`SignAggr::mus`].
+    ///
+    /// [`verify`]: crate::api::verify
+    pub fn output(&self) -> C::Scalar {
+        self.mus.iter().sum()
+    }
+
+    /// Whether `self` and `other` can be passed to [`combine`]. Always
+    /// `true`: `K` and the curve are fixed by the type, and [`combine`]
+    /// merges any two signer lists, keeping `self`'s order and appending
+    /// new signers, so there is no ordering the two could disagree on. It
+    /// exists so pipelines can state the check before combining.
+    ///
+    /// [`combine`]: crate::api::combine
+    pub fn compatible_with(&self, _other: &Self) -> bool {
+        true
     }
 
     #[cfg(test)]
-    pub(crate) fn gamma_mut(&mut self) -> &mut G1 {
+    pub(crate) fn gamma_mut(&mut self) -> &mut C::G1 {
         &mut self.gamma
     }
 
     #[cfg(test)]
-    pub(crate) fn mus_mut(&mut self) -> &mut Vec<Scalar> {
+    pub(crate) fn mus_mut(&mut self) -> &mut Vec<C::Scalar> {
         &mut self.mus
     }
+
+    /// `(id, gamma, mu)` if the aggregate has exactly one signer, the
+    /// compact single-signer view; `None` for zero or several signers.
+    pub fn as_single_signer(&self) -> Option<(Id<K>, &C::G1, &C::Scalar)> {
+        match (self.ord_ids.as_slice(), self.mus.as_slice()) {
+            ([id], [mu]) => Some((*id, &self.gamma, mu)),
+            _ => None,
+        }
+    }
+
+    /// Whether this is the trivial aggregate, e.g. of an empty program:
+    /// `gamma` is the group identity and every `mu` is zero.
+    pub fn is_identity(&self) -> bool {
+        self.gamma.is_zero() && self.mus.iter().all(C::Scalar::is_zero)
+    }
+
+    /// `(id, mu)` pairs in `ord_ids` order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Id<K>, &C::Scalar)> {
+        self.ord_ids.iter().zip(&self.mus)
+    }
+
+    /// Picks the key of every signer out of `pks`, which may be in any
+    /// order, returning them aligned with `ord_ids`. Errors if a signer has
+    /// no key or `pks` holds two keys for one id.
+    pub fn reorder_keys<'a>(
+        &self,
+        pks: &'a [PublicKey<K, C>],
+    ) -> Result<Vec<&'a PublicKey<K, C>>, ProtocolError> {
+        let mut by_id: HashMap<Id<K>, &'a PublicKey<K, C>> = HashMap::with_capacity(pks.len());
+        for pk in pks {
+            if by_id.insert(pk.id(), pk).is_some() {
+                return Err(ProtocolError::InvalidInput(
+                    "duplicate public key id".to_string(),
+                ));
+            }
+        }
+        self.ord_ids
+            .iter()
+            .map(|id| {
+                by_id.get(id).copied().ok_or_else(|| {
+                    ProtocolError::InvalidInput("missing public key for ord_id".to_string())
+                })
+            })
+            .collect()
+    }
+
+    /// Aggregated `mu` of signer `id`, or `None` if it is not in `ord_ids`.
+    pub fn mu_for(&self, id: &Id<K>) -> Option<&C::Scalar> {
+        let j = self.ord_ids.iter().position(|x| x == id)?;
+        self.mus.get(j)
+    }
+
+    /// Aggregate of the same program with every coefficient negated:
+    /// `gamma`, each `mu` and any retained partials flip sign. With
+    /// [`combine`] this gives subtraction.
+    ///
+    /// [`combine`]: crate::api::combine
+    pub fn negate(&self) -> SignAggr<K, C> {
+        Self {
+            gamma: -self.gamma,
+            ord_ids: self.ord_ids.clone(),
+            mus: self.mus.iter().map(|mu| -*mu).collect(),
+            partials: self
+                .partials
+                .as_ref()
+                .map(|p| p.iter().map(|g| -*g).collect()),
+            label_root: self.label_root,
+            program: self.program.as_ref().map(|p| LabeledProgram {
+                coeffs: p.coeffs.iter().map(|f| -*f).collect(),
+                labels: p.labels.clone(),
+            }),
+        }
+    }
+
+    /// Signer `id`'s contribution to `gamma`, `sum f_i * gamma_i` over its
+    /// terms. Only retained by [`eval_with_breakdown`] (and kept up to date
+    /// by [`SignAggr::add_term`]); `None` otherwise or if `id` is not a
+    /// signer. The partials sum to `gamma`.
+    ///
+    /// [`eval_with_breakdown`]: crate::api::eval_with_breakdown
+    pub fn partial_gamma(&self, id: &Id<K>) -> Option<&C::G1> {
+        let j = self.ord_ids.iter().position(|x| x == id)?;
+        self.partials.as_ref()?.get(j)
+    }
+
+    pub fn into_parts(self) -> (C::G1, Vec<C::Scalar>) {
+        (self.gamma, self.mus)
+    }
+
+    /// Folds one more term `coeff * share` into the aggregate, appending
+    /// `id` to `ord_ids` if this signer has not been seen yet.
+    ///
+    /// Building an aggregate term by term from an empty one yields the same
+    /// result as a single `eval` over the same terms in the same order. The
+    /// partial aggregate is the whole accumulator state, so a long-running
+    /// aggregation can be checkpointed with [`SignAggr::to_bytes`] and resumed
+    /// from [`SignAggr::from_bytes`]. Any embedded program or label root no
+    /// longer describes the aggregate and is dropped.
+    pub fn add_term(
+        &mut self,
+        coeff: C::Scalar,
+        id: Id<K>,
+        share: &SignShare<K, C>,
+    ) -> Result<(), ProtocolError> {
+        if share.id() != id {
+            return Err(ProtocolError::InvalidInput(
+                "share id does not match term id".to_string(),
+            ));
+        }
+
+        let gamma = *share.gamma() * coeff;
+        self.gamma += gamma;
+        self.program = None;
+        self.label_root = None;
+
+        let mu = coeff * share.mu();
+        match self.ord_ids.iter().position(|x| *x == id) {
+            Some(j) => {
+                self.mus[j] += mu;
+                if let Some(partials) = &mut self.partials {
+                    partials[j] += gamma;
+                }
+            }
+            None => {
+                self.ord_ids.push(id);
+                self.mus.push(mu);
+                if let Some(partials) = &mut self.partials {
+                    partials.push(gamma);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy with signers sorted by id bytes, `mus` (and any retained
+    /// partials) permuted to match and `gamma` unchanged. Aggregates over the
+    /// same terms become equal regardless of the order signers were first
+    /// seen, and still verify.
+    pub fn canonicalize(&self) -> SignAggr<K, C> {
+        let mut order: Vec<usize> = (0..self.ord_ids.len()).collect();
+        order.sort_by_key(|&j| self.ord_ids[j]);
+
+        Self {
+            gamma: self.gamma,
+            ord_ids: order.iter().map(|&j| self.ord_ids[j]).collect(),
+            mus: order.iter().map(|&j| self.mus[j]).collect(),
+            partials: self
+                .partials
+                .as_ref()
+                .map(|p| order.iter().map(|&j| p[j]).collect()),
+            label_root: self.label_root,
+            program: self.program.clone(),
+        }
+    }
+
+    /// Whether `total` is the [`combine`] of `parts`,
+    /// as a coordinator of a distributed aggregation would check. Signer
+    /// order is not compared, since it depends on the order parts are
+    /// combined in; `gamma` and every signer's `mu` must match. Errors if
+    /// `parts` is empty.
+    pub fn equals_combination(total: &Self, parts: &[Self]) -> Result<bool, ProtocolError> {
+        let (first, rest) = parts
+            .split_first()
+            .ok_or_else(|| ProtocolError::InvalidInput("no parts to combine".to_string()))?;
+        let combined = rest
+            .iter()
+            .fold(first.clone(), |acc, part| combine(&acc, part));
+        Ok(combined.canonicalize() == total.canonicalize())
+    }
+
+    /// Sub-aggregate over just the signers in `ids`, kept in `ord_ids`
+    /// order, with `gamma` rebuilt from their retained partial gammas. It
+    /// verifies against the slice of the program that carries those
+    /// signers' labels.
+    ///
+    /// Returns `None` if the aggregate has no retained partials (it was not
+    /// built with `eval_with_breakdown`) or an id is not among its signers.
+    pub fn project(&self, ids: &[Id<K>]) -> Option<SignAggr<K, C>> {
+        let partials = self.partials.as_ref()?;
+        if ids.iter().any(|id| !self.ord_ids.contains(id)) {
+            return None;
+        }
+
+        let keep: Vec<usize> = (0..self.ord_ids.len())
+            .filter(|&j| ids.contains(&self.ord_ids[j]))
+            .collect();
+        let sub_partials: Vec<C::G1> = keep.iter().map(|&j| partials[j]).collect();
+
+        Some(Self {
+            gamma: sub_partials.iter().sum(),
+            ord_ids: keep.iter().map(|&j| self.ord_ids[j]).collect(),
+            mus: keep.iter().map(|&j| self.mus[j]).collect(),
+            partials: Some(sub_partials),
+            label_root: None,
+            program: None,
+        })
+    }
+
+    /// Merkle root over the labels this aggregate covers, if it was built
+    /// with `eval_with_commitment`. Check membership with
+    /// [`verify_label_inclusion`](crate::commitment::verify_label_inclusion).
+    pub const fn label_root(&self) -> Option<&[u8; 32]> {
+        self.label_root.as_ref()
+    }
+
+    /// Program embedded by `eval_self_contained`, which
+    /// `verify_self_contained` checks against.
+    pub const fn program(&self) -> Option<&LabeledProgram<K, C>> {
+        self.program.as_ref()
+    }
+
+    pub(crate) fn set_program(&mut self, program: LabeledProgram<K, C>) {
+        self.program = Some(program);
+    }
+
+    pub(crate) fn set_label_root(&mut self, root: [u8; 32]) {
+        self.label_root = Some(root);
+    }
+
+    /// Retained per-signer partial gammas, aligned with `ord_ids`.
+    pub(crate) fn partials(&self) -> Option<&[C::G1]> {
+        self.partials.as_deref()
+    }
+
+    pub(crate) fn set_partials(&mut self, partials: Vec<C::G1>) {
+        debug_assert_eq!(partials.len(), self.ord_ids.len());
+        self.partials = Some(partials);
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct SignShare<const K: usize> {
+impl<const K: usize> SignAggr<K> {
+    /// Length of [`SignAggr::to_bytes`], computed without encoding.
+    pub fn serialized_len(&self) -> usize {
+        G1_COMPRESSED_LEN + 4 + self.ord_ids.len() * (K + SCALAR_LEN)
+    }
+
+    /// Encodes the aggregate as
+    /// `compressed(gamma) || n (u32 LE) || (id_j || canonical(mu_j))*`,
+    /// keeping the `ord_ids` order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.serialized_len());
+        self.gamma
+            .serialize_compressed(&mut out)
+            .expect("writing to a Vec cannot fail");
+        out.extend_from_slice(&(self.ord_ids.len() as u32).to_le_bytes());
+        for (id, mu) in self.ord_ids.iter().zip(&self.mus) {
+            out.extend_from_slice(&id.0);
+            out.extend_from_slice(&scalar_to_bytes(mu));
+        }
+        out
+    }
+
+    /// Decodes an aggregate produced by [`SignAggr::to_bytes`]. The signer
+    /// count must match the input length before anything is allocated, and
+    /// duplicate ids are rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        if bytes.len() < G1_COMPRESSED_LEN + 4 {
+            return Err(ProtocolError::InvalidInput(
+                "sign aggregate too short".to_string(),
+            ));
+        }
+        let (gamma_bytes, rest) = bytes.split_at(G1_COMPRESSED_LEN);
+        let (n, rest) = rest.split_at(4);
+        let n = u32::from_le_bytes(n.try_into().expect("split at 4")) as usize;
+
+        let entry_len = K + SCALAR_LEN;
+        if n.checked_mul(entry_len) != Some(rest.len()) {
+            return Err(ProtocolError::InvalidInput(
+                "sign aggregate length does not match signer count".to_string(),
+            ));
+        }
+
+        let gamma = G1::deserialize_compressed(gamma_bytes)
+            .map_err(|e| ProtocolError::InvalidInput(format!("invalid gamma: {e}")))?;
+
+        let mut ord_ids = Vec::with_capacity(n);
+        let mut mus = Vec::with_capacity(n);
+        let mut seen = HashSet::with_capacity(n);
+        for chunk in rest.chunks_exact(entry_len) {
+            let (id_bytes, mu_bytes) = chunk.split_at(K);
+            let id = Id(id_bytes.try_into().expect("split at K"));
+            if !seen.insert(id) {
+                return Err(ProtocolError::InvalidInput(
+                    "duplicate id in sign aggregate".to_string(),
+                ));
+            }
+            let mu = scalar_from_bytes(mu_bytes)
+                .map_err(|e| ProtocolError::InvalidInput(format!("invalid mu: {e}")))?;
+            ord_ids.push(id);
+            mus.push(mu);
+        }
+
+        Self::new(gamma, ord_ids, mus)
+    }
+}
+
+/// [`SignAggr`] whose id length `K` is only known at runtime, for the
+/// supported sizes 16 and 32.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DynAggr {
+    K16(SignAggr<16>),
+    K32(SignAggr<32>),
+}
+
+impl DynAggr {
+    pub const fn k(&self) -> usize {
+        match self {
+            Self::K16(_) => 16,
+            Self::K32(_) => 32,
+        }
+    }
+
+    /// Encodes the aggregate as `K as u8 || SignAggr::to_bytes()`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (k, body) = match self {
+            Self::K16(a) => (16u8, a.to_bytes()),
+            Self::K32(a) => (32u8, a.to_bytes()),
+        };
+        let mut out = Vec::with_capacity(1 + body.len());
+        out.push(k);
+        out.extend_from_slice(&body);
+        out
+    }
+
+    /// Decodes an aggregate produced by [`DynAggr::to_bytes`], erroring on an
+    /// unsupported `K`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        let (&k, body) = bytes
+            .split_first()
+            .ok_or_else(|| ProtocolError::InvalidInput("aggregate is empty".to_string()))?;
+        match k {
+            16 => Ok(Self::K16(SignAggr::from_bytes(body)?)),
+            32 => Ok(Self::K32(SignAggr::from_bytes(body)?)),
+            _ => Err(ProtocolError::InvalidInput(format!("unsupported K = {k}"))),
+        }
+    }
+}
+
+impl From<SignAggr<16>> for DynAggr {
+    fn from(aggr: SignAggr<16>) -> Self {
+        Self::K16(aggr)
+    }
+}
+
+impl From<SignAggr<32>> for DynAggr {
+    fn from(aggr: SignAggr<32>) -> Self {
+        Self::K32(aggr)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignShare<const K: usize, C: Curve = DefaultCurve> {
     id: Id<K>,
-    gamma: G1,
-    mu: Scalar,
+    gamma: C::G1,
+    mu: C::Scalar,
 }
 
-impl<const K: usize> SignShare<K> {
-    pub const fn new(id: Id<K>, gamma: G1, mu: Scalar) -> Self {
+impl<const K: usize, C: Curve> SignShare<K, C> {
+    pub const fn new(id: Id<K>, gamma: C::G1, mu: C::Scalar) -> Self {
         Self { id, gamma, mu }
     }
 
@@ -152,23 +764,91 @@ impl<const K: usize> SignShare<K> {
         self.id
     }
 
-    pub fn gamma(&self) -> &G1 {
+    pub fn gamma(&self) -> &C::G1 {
         &self.gamma
     }
 
-    pub fn mu(&self) -> &Scalar {
+    pub fn mu(&self) -> &C::Scalar {
         &self.mu
     }
+
+    pub fn gamma_affine(&self) -> <C::G1 as CurveGroup>::Affine {
+        self.gamma.into_affine()
+    }
+
+    /// Whether both shares carry the same message `mu`, compared in constant
+    /// time. Ids and gammas are ignored, so the same value signed under
+    /// different tags is detected.
+    pub fn is_same_message(&self, other: &Self) -> bool {
+        scalar_ct_eq(&self.mu, &other.mu).into()
+    }
+
+    /// Keeps the first share of every distinct message, in input order.
+    pub fn dedup_by_message(shares: Vec<Self>) -> Vec<Self> {
+        let mut kept: Vec<Self> = Vec::with_capacity(shares.len());
+        for share in shares {
+            if !kept.iter().any(|k| k.is_same_message(&share)) {
+                kept.push(share);
+            }
+        }
+        kept
+    }
+}
+
+impl<const K: usize> SignShare<K> {
+    /// Builds a share from an affine `gamma`, e.g. one held by other
+    /// arkworks code.
+    pub fn from_affine(id: Id<K>, gamma: G1Affine, mu: Scalar) -> Self {
+        Self::new(id, gamma.into(), mu)
+    }
+
+    /// Encoded length of a share: `K + 48 + 32` bytes.
+    pub const SERIALIZED_LEN: usize = K + G1_COMPRESSED_LEN + SCALAR_LEN;
+
+    /// Encodes the share as `id || compressed(gamma) || canonical(mu)`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::SERIALIZED_LEN);
+        out.extend_from_slice(&self.id.0);
+        self.gamma
+            .serialize_compressed(&mut out)
+            .expect("writing to a Vec cannot fail");
+        out.extend_from_slice(&scalar_to_bytes(&self.mu));
+        out
+    }
+
+    /// Decodes a share produced by [`SignShare::to_bytes`], rejecting a
+    /// `gamma` that is not on the curve or not in the prime-order subgroup.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        if bytes.len() != Self::SERIALIZED_LEN {
+            return Err(ProtocolError::InvalidInput(format!(
+                "sign share must be {} bytes, got {}",
+                Self::SERIALIZED_LEN,
+                bytes.len()
+            )));
+        }
+        let (id_bytes, rest) = bytes.split_at(K);
+        let (gamma_bytes, mu_bytes) = rest.split_at(G1_COMPRESSED_LEN);
+
+        let id = Id(id_bytes.try_into().expect("length checked above"));
+        let gamma = G1::deserialize_compressed(gamma_bytes)
+            .map_err(|e| ProtocolError::InvalidInput(format!("invalid gamma: {e}")))?;
+        // non-canonical encodings (integers >= r) are rejected, so a stored
+        // mu has exactly one byte representation
+        let mu = scalar_from_bytes(mu_bytes)
+            .map_err(|e| ProtocolError::InvalidInput(format!("invalid mu: {e}")))?;
+
+        Ok(Self::new(id, gamma, mu))
+    }
 }
 
 #[derive(Clone, Debug)]
-pub struct LabeledProgram<const K: usize> {
-    coeffs: Vec<Scalar>,
+pub struct LabeledProgram<const K: usize, C: Curve = DefaultCurve> {
+    coeffs: Vec<C::Scalar>,
     labels: Vec<Label<K>>,
 }
 
-impl<const K: usize> LabeledProgram<K> {
-    pub fn new(coeffs: Vec<Scalar>, labels: Vec<Label<K>>) -> Result<Self, ProtocolError> {
+impl<const K: usize, C: Curve> LabeledProgram<K, C> {
+    pub fn new(coeffs: Vec<C::Scalar>, labels: Vec<Label<K>>) -> Result<Self, ProtocolError> {
         if coeffs.len() != labels.len() {
             return Err(ProtocolError::InvalidInput(
                 "coeffs and labels length mismatch".to_string(),
@@ -181,11 +861,1222 @@ impl<const K: usize> LabeledProgram<K> {
         self.coeffs.len()
     }
 
-    pub fn coeffs(&self) -> &[Scalar] {
+    pub fn coeffs(&self) -> &[C::Scalar] {
         &self.coeffs
     }
 
     pub fn labels(&self) -> &[Label<K>] {
         &self.labels
     }
+
+    /// Like [`LabeledProgram::new`], but also rejects labels with an all-zero
+    /// `Id`, which usually means the signer's key was never generated.
+    pub fn new_strict(
+        coeffs: Vec<C::Scalar>,
+        labels: Vec<Label<K>>,
+    ) -> Result<Self, ProtocolError> {
+        if let Some(i) = labels.iter().position(|l| l.id().is_zero()) {
+            return Err(ProtocolError::InvalidInput(format!(
+                "label {i} has an all-zero id"
+            )));
+        }
+        Self::new(coeffs, labels)
+    }
+
+    /// Inner product `sum_i weights[i] * m_i` of the signed data with a
+    /// public weight vector. Errors if the lengths differ.
+    pub fn dot(weights: &[C::Scalar], labels: Vec<Label<K>>) -> Result<Self, ProtocolError> {
+        Self::new(weights.to_vec(), labels)
+    }
+
+    /// Program computing the mean of the labeled messages: every coefficient
+    /// is $n^{-1}$ in the field (not an integer division). Errors if `labels`
+    /// is empty.
+    pub fn average(labels: Vec<Label<K>>) -> Result<Self, ProtocolError> {
+        let inv_n = C::Scalar::from(labels.len() as u64)
+            .inverse()
+            .ok_or_else(|| ProtocolError::InvalidInput("cannot average zero labels".to_string()))?;
+        Self::new(vec![inv_n; labels.len()], labels)
+    }
+
+    /// Splits the program into consecutive sub-programs of at most `size`
+    /// terms, for map-reduce style evaluation. Folding the chunk aggregates
+    /// with [`combine`] in chunk order reproduces `eval` of the whole
+    /// program, signer order included. Errors if `size` is zero.
+    ///
+    /// [`combine`]: crate::api::combine
+    pub fn chunk(&self, size: usize) -> Result<Vec<LabeledProgram<K, C>>, ProtocolError> {
+        if size == 0 {
+            return Err(ProtocolError::InvalidInput(
+                "chunk size must be positive".to_string(),
+            ));
+        }
+        Ok(self
+            .coeffs
+            .chunks(size)
+            .zip(self.labels.chunks(size))
+            .map(|(coeffs, labels)| Self {
+                coeffs: coeffs.to_vec(),
+                labels: labels.to_vec(),
+            })
+            .collect())
+    }
+
+    /// Number of distinct signer ids among the labels.
+    pub fn num_distinct_signers(&self) -> usize {
+        self.labels
+            .iter()
+            .map(Label::id)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Distinct signer ids of the program with no key in `have`, in order of
+    /// first appearance: the keys to fetch before [`verify`] can run.
+    ///
+    /// [`verify`]: crate::api::verify
+    pub fn missing_keys(&self, have: &HashMap<Id<K>, PublicKey<K, C>>) -> Vec<Id<K>> {
+        let mut seen = HashSet::new();
+        self.labels
+            .iter()
+            .map(Label::id)
+            .filter(|id| !have.contains_key(id) && seen.insert(*id))
+            .collect()
+    }
+
+    /// SHA-256 over the canonical encoding of every `(coeff, label)` term in
+    /// order, under a dedicated DST. Reordering terms changes the fingerprint.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new()
+            .chain_update(DST_PROGRAM_FINGERPRINT)
+            .chain_update((self.n() as u64).to_le_bytes());
+        let mut buf = Vec::with_capacity(SCALAR_LEN);
+        for (f, lab) in self.coeffs.iter().zip(&self.labels) {
+            buf.clear();
+            f.serialize_compressed(&mut buf)
+                .expect("writing to a Vec cannot fail");
+            hasher.update(&buf);
+            hasher.update(lab.to_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// Returns a copy of the program with every zero-coefficient term removed,
+    /// preserving the order of the remaining terms.
+    pub fn prune_zeros(&self) -> LabeledProgram<K, C> {
+        let (coeffs, labels) = self
+            .coeffs
+            .iter()
+            .zip(self.labels.iter())
+            .filter(|(f, _)| !f.is_zero())
+            .map(|(f, l)| (*f, *l))
+            .unzip();
+        Self { coeffs, labels }
+    }
+
+    /// Hashes every label into `G1` once under `pp`, for repeated
+    /// verification with [`verify_prepared`](crate::api::verify_prepared).
+    pub fn prepare(&self, pp: &Params<K, C>) -> Result<PreparedProgram<K, C>, ProtocolError> {
+        let label_bytes: Vec<Vec<u8>> = self.labels.iter().map(Label::to_bytes).collect();
+        let msgs: Vec<&[u8]> = label_bytes.iter().map(Vec::as_slice).collect();
+        let label_points = C::hash_to_g1_batch(pp.h2g1_label(), &msgs)?;
+
+        Ok(PreparedProgram {
+            dst_fingerprint: pp.dst_fingerprint(),
+            program: self.clone(),
+            label_points,
+        })
+    }
+}
+
+impl<const K: usize> LabeledProgram<K> {
+    /// [`LabeledProgram::new`] with unsigned integer coefficients.
+    pub fn from_u64_coeffs(coeffs: Vec<u64>, labels: Vec<Label<K>>) -> Result<Self, ProtocolError> {
+        Self::new(coeffs.into_iter().map(Scalar::from).collect(), labels)
+    }
+
+    /// [`LabeledProgram::new`] with signed integer coefficients; a negative
+    /// `c` becomes `r - |c|` in the field.
+    pub fn from_i64_coeffs(coeffs: Vec<i64>, labels: Vec<Label<K>>) -> Result<Self, ProtocolError> {
+        Self::new(
+            coeffs
+                .into_iter()
+                .map(|c| scalar_from_i128(c.into()))
+                .collect(),
+            labels,
+        )
+    }
+}
+
+/// A [`LabeledProgram`] with each label's hash $H(\ell_i)$ cached, built with
+/// [`LabeledProgram::prepare`]. Tied to the params it was prepared under via
+/// their DST fingerprint.
+#[derive(Clone, Debug)]
+pub struct PreparedProgram<const K: usize, C: Curve = DefaultCurve> {
+    dst_fingerprint: [u8; 32],
+    program: LabeledProgram<K, C>,
+    label_points: Vec<C::G1>,
+}
+
+impl<const K: usize, C: Curve> PreparedProgram<K, C> {
+    pub fn program(&self) -> &LabeledProgram<K, C> {
+        &self.program
+    }
+
+    /// $H(\ell_i)$ for every label, in program order.
+    pub fn label_points(&self) -> &[C::G1] {
+        &self.label_points
+    }
+
+    pub const fn dst_fingerprint(&self) -> &[u8; 32] {
+        &self.dst_fingerprint
+    }
+}
+
+impl<const K: usize> PreparedProgram<K> {
+    /// Encodes the prepared program as
+    /// `dst_fingerprint || n (u32 LE) || (canonical(f_i) || label_i || compressed(H(ell_i)))*`,
+    /// so the label hashing is done once and reloaded with
+    /// [`PreparedProgram::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let n = self.program.n();
+        let mut out = Vec::with_capacity(32 + 4 + n * Self::ENTRY_LEN);
+        out.extend_from_slice(&self.dst_fingerprint);
+        out.extend_from_slice(&(n as u32).to_le_bytes());
+        for ((f, lab), h) in self
+            .program
+            .coeffs
+            .iter()
+            .zip(&self.program.labels)
+            .zip(&self.label_points)
+        {
+            out.extend_from_slice(&scalar_to_bytes(f));
+            out.extend_from_slice(&lab.to_bytes());
+            h.serialize_compressed(&mut out)
+                .expect("writing to a Vec cannot fail");
+        }
+        out
+    }
+
+    /// Decodes a prepared program produced by [`PreparedProgram::to_bytes`],
+    /// checking that every point is a valid `G1` element. The points are not
+    /// re-hashed, which is the point of persisting them, so only load bytes
+    /// from storage as trusted as the verifier itself; the DST fingerprint is
+    /// still checked against the params at verification time.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        if bytes.len() < 32 + 4 {
+            return Err(ProtocolError::InvalidInput(
+                "prepared program too short".to_string(),
+            ));
+        }
+        let (fp, rest) = bytes.split_at(32);
+        let (n, rest) = rest.split_at(4);
+        let n = u32::from_le_bytes(n.try_into().expect("split at 4")) as usize;
+        if n.checked_mul(Self::ENTRY_LEN) != Some(rest.len()) {
+            return Err(ProtocolError::InvalidInput(
+                "prepared program length does not match term count".to_string(),
+            ));
+        }
+
+        let mut coeffs = Vec::with_capacity(n);
+        let mut labels = Vec::with_capacity(n);
+        let mut label_points = Vec::with_capacity(n);
+        for chunk in rest.chunks_exact(Self::ENTRY_LEN) {
+            let (f, rest) = chunk.split_at(SCALAR_LEN);
+            let (id, rest) = rest.split_at(K);
+            let (tag, h) = rest.split_at(K);
+            coeffs.push(
+                scalar_from_bytes(f)
+                    .map_err(|e| ProtocolError::InvalidInput(format!("invalid coeff: {e}")))?,
+            );
+            labels.push(Label::new(
+                Id(id.try_into().expect("split at K")),
+                Tag(tag.try_into().expect("split at K")),
+            ));
+            label_points.push(
+                G1::deserialize_compressed(h).map_err(|e| {
+                    ProtocolError::InvalidInput(format!("invalid label point: {e}"))
+                })?,
+            );
+        }
+
+        Ok(Self {
+            dst_fingerprint: fp.try_into().expect("split at 32"),
+            program: LabeledProgram::new(coeffs, labels)?,
+            label_points,
+        })
+    }
+
+    const ENTRY_LEN: usize = SCALAR_LEN + 2 * K + G1_COMPRESSED_LEN;
+}
+
+/// One aggregate to check in a batch: its program, the claimed output and
+/// the aggregate itself.
+#[derive(Clone, Copy, Debug)]
+pub struct BatchItem<'a, const K: usize, C: Curve = DefaultCurve> {
+    pub program: &'a LabeledProgram<K, C>,
+    pub msg: C::Scalar,
+    pub sign_aggr: &'a SignAggr<K, C>,
+}
+
+impl<'a, const K: usize, C: Curve> BatchItem<'a, K, C> {
+    pub const fn new(
+        program: &'a LabeledProgram<K, C>,
+        msg: C::Scalar,
+        sign_aggr: &'a SignAggr<K, C>,
+    ) -> Self {
+        Self {
+            program,
+            msg,
+            sign_aggr,
+        }
+    }
+}
+
+/// Verification inputs for aggregates of one id length `K`, registered with
+/// a [`DynVerifier`].
+#[derive(Clone, Copy)]
+pub struct DynInputs<'a, const K: usize> {
+    pub pp: &'a Params<K>,
+    pub program: &'a LabeledProgram<K>,
+    pub pks: &'a HashMap<Id<K>, PublicKey<K>>,
+}
+
+impl<'a, const K: usize> DynInputs<'a, K> {
+    pub const fn new(
+        pp: &'a Params<K>,
+        program: &'a LabeledProgram<K>,
+        pks: &'a HashMap<Id<K>, PublicKey<K>>,
+    ) -> Self {
+        Self { pp, program, pks }
+    }
+}
+
+/// Inputs per supported `K` for [`verify_dyn`]; a `K` left `None` is
+/// rejected when an aggregate of that size arrives.
+///
+/// [`verify_dyn`]: crate::api::verify_dyn
+#[derive(Clone, Copy, Default)]
+pub struct DynVerifier<'a> {
+    pub k16: Option<DynInputs<'a, 16>>,
+    pub k32: Option<DynInputs<'a, 32>>,
+}
+
+/// Linear program over `labels` that stores only its nonzero coefficients as
+/// `(label index, coeff)` pairs, sorted by index.
+#[derive(Clone, Debug)]
+pub struct SparseProgram<const K: usize, C: Curve = DefaultCurve> {
+    terms: Vec<(usize, C::Scalar)>,
+    labels: Vec<Label<K>>,
+}
+
+impl<const K: usize, C: Curve> SparseProgram<K, C> {
+    /// Builds a sparse program, dropping zero coefficients. Errors if an
+    /// index is out of range for `labels` or appears more than once.
+    pub fn new(
+        terms: Vec<(usize, C::Scalar)>,
+        labels: Vec<Label<K>>,
+    ) -> Result<Self, ProtocolError> {
+        let mut terms: Vec<(usize, C::Scalar)> =
+            terms.into_iter().filter(|(_, f)| !f.is_zero()).collect();
+        terms.sort_unstable_by_key(|(i, _)| *i);
+
+        if terms.last().is_some_and(|(i, _)| *i >= labels.len()) {
+            return Err(ProtocolError::InvalidInput(
+                "sparse term index out of range".to_string(),
+            ));
+        }
+        if terms.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(ProtocolError::InvalidInput(
+                "duplicate sparse term index".to_string(),
+            ));
+        }
+
+        Ok(Self { terms, labels })
+    }
+
+    pub fn from_dense(program: &LabeledProgram<K, C>) -> Self {
+        let terms = program
+            .coeffs()
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| !f.is_zero())
+            .map(|(i, f)| (i, *f))
+            .collect();
+        Self {
+            terms,
+            labels: program.labels().to_vec(),
+        }
+    }
+
+    pub fn to_dense(&self) -> LabeledProgram<K, C> {
+        let mut coeffs = vec![C::Scalar::zero(); self.labels.len()];
+        for &(i, f) in &self.terms {
+            coeffs[i] = f;
+        }
+        LabeledProgram {
+            coeffs,
+            labels: self.labels.clone(),
+        }
+    }
+
+    /// Number of labels, i.e. the length of the equivalent dense program.
+    pub fn n(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn terms(&self) -> &[(usize, C::Scalar)] {
+        &self.terms
+    }
+
+    pub fn labels(&self) -> &[Label<K>] {
+        &self.labels
+    }
+}
+
+/// Adds hex and base64 text encodings on top of a type's `to_bytes` /
+/// `from_bytes`.
+macro_rules! impl_text_encodings {
+    ($($ty:ident),* $(,)?) => {$(
+        impl<const K: usize> $ty<K> {
+            /// Lowercase hex of [`Self::to_bytes`].
+            pub fn to_hex(&self) -> String {
+                hex::encode(self.to_bytes())
+            }
+
+            pub fn from_hex(s: &str) -> Result<Self, ProtocolError> {
+                let bytes = hex::decode(s)
+                    .map_err(|e| ProtocolError::InvalidInput(format!("invalid hex: {e}")))?;
+                Self::from_bytes(&bytes)
+            }
+
+            /// Standard padded base64 of [`Self::to_bytes`].
+            pub fn to_base64(&self) -> String {
+                BASE64.encode(self.to_bytes())
+            }
+
+            pub fn from_base64(s: &str) -> Result<Self, ProtocolError> {
+                let bytes = BASE64
+                    .decode(s)
+                    .map_err(|e| ProtocolError::InvalidInput(format!("invalid base64: {e}")))?;
+                Self::from_bytes(&bytes)
+            }
+        }
+    )*};
+}
+
+impl_text_encodings!(PublicKey, SignShare, SignAggr);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{algebra::g1_gen, params::Params, protocol::keygen, protocol::sign};
+    use ark_std::{UniformRand, test_rng};
+
+    fn share<const K: usize>() -> SignShare<K> {
+        let pp = Params::<K>::new();
+        let mut rng = test_rng();
+        let (sk, _pk) = keygen(&pp, &mut rng).unwrap();
+        let label = Label::new(sk.id(), Tag([7u8; K]));
+        sign(&pp, &sk, label, Scalar::rand(&mut rng)).unwrap()
+    }
+
+    mod send_sync_tests {
+
+        use super::*;
+
+        fn _assert_send_sync<T: Send + Sync>() {}
+
+        #[test]
+        fn shared_types_are_send_sync() {
+            _assert_send_sync::<Params<8>>();
+            _assert_send_sync::<PublicKey<8>>();
+            _assert_send_sync::<PreparedPublicKey<8>>();
+            _assert_send_sync::<SignShare<8>>();
+            _assert_send_sync::<SignAggr<8>>();
+            _assert_send_sync::<LabeledProgram<8>>();
+        }
+    }
+
+    mod id_ord_tests {
+
+        use super::*;
+
+        #[test]
+        fn ordering_is_lexicographic() {
+            let ids = [[0u8, 9], [1, 0], [0, 10], [255, 0], [1, 1]].map(Id::<2>);
+            for a in &ids {
+                for b in &ids {
+                    assert_eq!(a.cmp(b), a.0.cmp(&b.0));
+                }
+            }
+
+            let map: std::collections::BTreeMap<_, _> = ids.iter().map(|id| (*id, ())).collect();
+            let sorted: Vec<_> = map.into_keys().collect();
+            assert_eq!(sorted, [[0, 9], [0, 10], [1, 0], [1, 1], [255, 0]].map(Id));
+        }
+    }
+
+    mod tag_derive_tests {
+
+        use super::*;
+
+        #[test]
+        fn deterministic() {
+            let a = Tag::<32>::derive(b"dataset", b"record-5");
+            let b = Tag::<32>::derive(b"dataset", b"record-5");
+            assert_eq!(a.0, b.0);
+        }
+
+        #[test]
+        fn domain_separated() {
+            let a = Tag::<32>::derive(b"dataset-a", b"record-5");
+            let b = Tag::<32>::derive(b"dataset-b", b"record-5");
+            assert_ne!(a.0, b.0);
+
+            // moving bytes between domain and input must not collide
+            let c = Tag::<32>::derive(b"ab", b"c");
+            let d = Tag::<32>::derive(b"a", b"bc");
+            assert_ne!(c.0, d.0);
+        }
+
+        #[test]
+        fn distinct_inputs_give_distinct_tags() {
+            let tags: std::collections::HashSet<[u8; 8]> = (0u32..1000)
+                .map(|i| Tag::<8>::derive(b"dataset", &i.to_be_bytes()).0)
+                .collect();
+            assert_eq!(tags.len(), 1000);
+        }
+
+        #[test]
+        fn any_length() {
+            let short = Tag::<4>::derive(b"d", b"x");
+            let long = Tag::<80>::derive(b"d", b"x");
+            // counter-mode output is a prefix-consistent stream
+            assert_eq!(short.0, long.0[..4]);
+            assert_ne!(long.0[32..64], long.0[..32]);
+        }
+    }
+
+    mod rotate_tests {
+
+        use super::*;
+
+        #[test]
+        fn rotated_key_keeps_id_but_not_value() {
+            let pp = Params::<8>::new();
+            let mut rng = test_rng();
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+
+            let (sk2, pk2) = sk.rotate(&mut rng);
+
+            assert_eq!(sk2.id(), sk.id());
+            assert_eq!(pk2.id(), pk.id());
+            assert_ne!(sk2.value(), sk.value());
+            assert_ne!(pk2.value(), pk.value());
+            assert_eq!(*pk2.value(), crate::algebra::g2_gen() * sk2.value());
+        }
+    }
+
+    mod new_strict_tests {
+
+        use super::*;
+
+        #[test]
+        fn all_zero_id_rejected_only_by_strict() {
+            let good = Label::new(Id([5u8; 8]), Tag([1u8; 8]));
+            let zero = Label::new(Id([0u8; 8]), Tag([2u8; 8]));
+            let coeffs = vec![Scalar::from(1), Scalar::from(2)];
+
+            assert!(LabeledProgram::<8>::new(coeffs.clone(), vec![good, zero]).is_ok());
+
+            let err =
+                LabeledProgram::<8>::new_strict(coeffs.clone(), vec![good, zero]).unwrap_err();
+            assert!(err.to_string().contains("label 1"));
+
+            assert!(LabeledProgram::<8>::new_strict(coeffs, vec![good, good]).is_ok());
+        }
+
+        #[test]
+        fn length_mismatch_still_checked() {
+            let good = Label::new(Id([5u8; 8]), Tag([1u8; 8]));
+            assert!(
+                LabeledProgram::<8>::new_strict(vec![Scalar::from(1)], vec![good, good]).is_err()
+            );
+        }
+    }
+
+    mod label_from_str_tests {
+
+        use super::*;
+
+        #[test]
+        fn stable_and_distinct() {
+            let a = Label::<8>::from_str_pair("alice", "record-5");
+            assert_eq!(a, Label::<8>::from_str_pair("alice", "record-5"));
+            assert_ne!(a, Label::<8>::from_str_pair("alice", "record-6"));
+            assert_ne!(a, Label::<8>::from_str_pair("bob", "record-5"));
+
+            // equal strings still land in separate id and tag spaces
+            let same = Label::<32>::from_str_pair("x", "x");
+            assert_ne!(same.id().0, same.tag().0);
+            assert_eq!(Label::<40>::from_str_pair("alice", "").id().0.len(), 40);
+        }
+    }
+
+    mod missing_keys_tests {
+
+        use super::*;
+
+        #[test]
+        fn reports_absent_signers_once() {
+            let pp = Params::<8>::new();
+            let mut rng = test_rng();
+            let keys: Vec<_> = (0..3).map(|_| keygen(&pp, &mut rng).unwrap().1).collect();
+
+            let labels: Vec<_> = [2, 0, 1, 2, 1]
+                .into_iter()
+                .map(|s| Label::new(keys[s].id(), Tag([s as u8; 8])))
+                .collect();
+            let program = LabeledProgram::new(vec![Scalar::from(1); 5], labels).unwrap();
+
+            let have = HashMap::from([(keys[0].id(), keys[0].clone())]);
+            assert_eq!(
+                program.missing_keys(&have),
+                vec![keys[2].id(), keys[1].id()]
+            );
+
+            let all: HashMap<_, _> = keys.iter().map(|pk| (pk.id(), pk.clone())).collect();
+            assert!(program.missing_keys(&all).is_empty());
+        }
+    }
+
+    mod fingerprint_tests {
+
+        use super::*;
+
+        fn program() -> LabeledProgram<8> {
+            let labels = (0u8..3)
+                .map(|i| Label::new(Id([i; 8]), Tag([i + 10; 8])))
+                .collect();
+            let coeffs = vec![Scalar::from(2), Scalar::from(3), Scalar::from(5)];
+            LabeledProgram::new(coeffs, labels).unwrap()
+        }
+
+        #[test]
+        fn identical_programs_share_fingerprint() {
+            assert_eq!(program().fingerprint(), program().fingerprint());
+        }
+
+        #[test]
+        fn swapping_terms_changes_fingerprint() {
+            let p = program();
+            let mut coeffs = p.coeffs().to_vec();
+            let mut labels = p.labels().to_vec();
+            coeffs.swap(0, 2);
+            labels.swap(0, 2);
+            let swapped = LabeledProgram::<8>::new(coeffs, labels).unwrap();
+            assert_ne!(p.fingerprint(), swapped.fingerprint());
+        }
+
+        #[test]
+        fn coeff_or_label_change_changes_fingerprint() {
+            let p = program();
+
+            let mut coeffs = p.coeffs().to_vec();
+            coeffs[1] += Scalar::from(1);
+            let other_coeff = LabeledProgram::<8>::new(coeffs, p.labels().to_vec()).unwrap();
+            assert_ne!(p.fingerprint(), other_coeff.fingerprint());
+
+            let mut labels = p.labels().to_vec();
+            labels[1] = Label::new(labels[1].id(), Tag([99; 8]));
+            let other_label = LabeledProgram::<8>::new(p.coeffs().to_vec(), labels).unwrap();
+            assert_ne!(p.fingerprint(), other_label.fingerprint());
+
+            // no zero terms, so pruning leaves the program unchanged
+            assert_eq!(p.fingerprint(), p.prune_zeros().fingerprint());
+        }
+    }
+
+    mod public_key_bytes_tests {
+
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let pp = Params::<8>::new();
+            let (_sk, pk) = keygen(&pp, &mut test_rng()).unwrap();
+
+            let bytes = pk.to_bytes();
+            assert_eq!(bytes.len(), 8 + G2_COMPRESSED_LEN);
+
+            let back = PublicKey::<8>::from_bytes(&bytes).unwrap();
+            assert_eq!(back.id(), pk.id());
+            assert_eq!(back.value(), pk.value());
+        }
+
+        fn check_len<const K: usize>() {
+            let pp = Params::<K>::new();
+            let (_sk, pk) = keygen(&pp, &mut test_rng()).unwrap();
+            assert_eq!(pk.to_bytes().len(), PublicKey::<K>::SERIALIZED_LEN);
+        }
+
+        #[test]
+        fn serialized_len_matches_to_bytes() {
+            check_len::<1>();
+            check_len::<8>();
+            check_len::<16>();
+            check_len::<32>();
+        }
+
+        #[test]
+        fn write_to_fixed_buffer() {
+            let pp = Params::<8>::new();
+            let (_sk, pk) = keygen(&pp, &mut test_rng()).unwrap();
+
+            let mut buf = [0u8; PublicKey::<8>::SERIALIZED_LEN];
+            pk.write_to(&mut buf).unwrap();
+            assert_eq!(buf.as_slice(), pk.to_bytes());
+            assert!(pk.write_to(&mut buf[1..]).is_err());
+        }
+
+        #[test]
+        fn corrupted_value_is_rejected() {
+            let pp = Params::<8>::new();
+            let (_sk, pk) = keygen(&pp, &mut test_rng()).unwrap();
+
+            let mut bytes = pk.to_bytes();
+            bytes[8 + G2_COMPRESSED_LEN - 1] ^= 0x01;
+            assert!(PublicKey::<8>::from_bytes(&bytes).is_err());
+            assert!(PublicKey::<8>::from_bytes(&bytes[..8]).is_err());
+        }
+
+        #[test]
+        fn registration_commitment_binds_key() {
+            let pp = Params::<8>::new();
+            let mut rng = test_rng();
+            let (_sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let (_sk2, other) = keygen(&pp, &mut rng).unwrap();
+
+            let c = pk.registration_commitment();
+            assert_eq!(c, pk.registration_commitment());
+            assert_ne!(c, other.registration_commitment());
+            // same value under another id commits differently
+            let relabeled = PublicKey::new(Id([0xAB; 8]), *pk.value());
+            assert_ne!(c, relabeled.registration_commitment());
+        }
+    }
+
+    mod affine_tests {
+
+        use super::*;
+
+        #[test]
+        fn round_trip_through_affine() {
+            let pp = Params::<8>::new();
+            let (_, pk) = keygen(&pp, &mut test_rng()).unwrap();
+            let back = PublicKey::from_affine(pk.id(), pk.value_affine());
+            assert_eq!(back, pk);
+            assert_eq!(G2::from(back.value_affine()), *pk.value());
+
+            let sh = share::<8>();
+            let back = SignShare::from_affine(sh.id(), sh.gamma_affine(), *sh.mu());
+            assert_eq!(back, sh);
+            assert_eq!(back.gamma_affine(), sh.gamma().into_affine());
+        }
+    }
+
+    mod sign_share_bytes_tests {
+
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let sh = share::<8>();
+            let bytes = sh.to_bytes();
+            assert_eq!(bytes.len(), SignShare::<8>::SERIALIZED_LEN);
+
+            let back = SignShare::<8>::from_bytes(&bytes).unwrap();
+            assert_eq!(back, sh);
+        }
+
+        #[test]
+        fn corrupted_gamma_is_rejected() {
+            let sh = share::<8>();
+            let mut bytes = sh.to_bytes();
+            bytes[8 + G1_COMPRESSED_LEN - 1] ^= 0x01;
+            assert!(SignShare::<8>::from_bytes(&bytes).is_err());
+        }
+
+        #[test]
+        fn non_canonical_mu_is_rejected() {
+            use ark_ff::{BigInteger, PrimeField};
+
+            let sh = SignShare::<8>::new(Id([1u8; 8]), g1_gen(), Scalar::from(3));
+            let bytes = sh.to_bytes();
+            let mu_at = 8 + G1_COMPRESSED_LEN;
+
+            let modulus = Scalar::MODULUS;
+            let mut modulus_plus_one = modulus;
+            modulus_plus_one.add_with_carry(&1u64.into());
+
+            let encodings = [
+                modulus.to_bytes_le(),
+                modulus_plus_one.to_bytes_le(),
+                vec![0xff; SCALAR_LEN],
+            ];
+            for enc in encodings {
+                let mut bad = bytes.clone();
+                bad[mu_at..].copy_from_slice(&enc);
+                assert!(matches!(
+                    SignShare::<8>::from_bytes(&bad),
+                    Err(ProtocolError::InvalidInput(_))
+                ));
+            }
+
+            // r - 1 is the largest canonical value and must still decode
+            let mut max = modulus;
+            max.sub_with_borrow(&1u64.into());
+            let mut ok = bytes.clone();
+            ok[mu_at..].copy_from_slice(&max.to_bytes_le());
+            let decoded = SignShare::<8>::from_bytes(&ok).unwrap();
+            assert_eq!(*decoded.mu(), -Scalar::from(1));
+        }
+
+        #[test]
+        fn wrong_length_is_rejected() {
+            let sh = SignShare::<8>::new(Id([1u8; 8]), g1_gen(), Scalar::from(3));
+            let bytes = sh.to_bytes();
+            assert!(SignShare::<8>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+            assert!(SignShare::<16>::from_bytes(&bytes).is_err());
+        }
+    }
+
+    mod single_signer_tests {
+
+        use super::*;
+
+        #[test]
+        fn only_for_one_signer() {
+            let gamma = g1_gen() * Scalar::from(3);
+            let one = SignAggr::<8>::new(gamma, vec![Id([1u8; 8])], vec![Scalar::from(4)]).unwrap();
+            assert_eq!(
+                one.as_single_signer(),
+                Some((Id([1u8; 8]), &gamma, &Scalar::from(4)))
+            );
+
+            let two = SignAggr::<8>::new(
+                gamma,
+                vec![Id([1u8; 8]), Id([2u8; 8])],
+                vec![Scalar::from(4), Scalar::from(5)],
+            )
+            .unwrap();
+            assert!(two.as_single_signer().is_none());
+            let empty = SignAggr::<8>::new(G1::zero(), vec![], vec![]).unwrap();
+            assert!(empty.as_single_signer().is_none());
+        }
+    }
+
+    mod equals_combination_tests {
+
+        use super::*;
+
+        fn part(gamma: u64, signers: &[(u8, u64)]) -> SignAggr<8> {
+            SignAggr::new(
+                g1_gen() * Scalar::from(gamma),
+                signers.iter().map(|(i, _)| Id([*i; 8])).collect(),
+                signers.iter().map(|(_, mu)| Scalar::from(*mu)).collect(),
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn detects_bad_splits() {
+            let parts = [part(5, &[(1, 2), (2, 3)]), part(7, &[(2, 4), (3, 1)])];
+
+            let total = part(12, &[(1, 2), (2, 7), (3, 1)]);
+            assert!(SignAggr::equals_combination(&total, &parts).unwrap());
+            // signer order does not matter
+            let reordered = part(12, &[(3, 1), (1, 2), (2, 7)]);
+            assert!(SignAggr::equals_combination(&reordered, &parts).unwrap());
+
+            // same output, mu moved between signers
+            let shifted = part(12, &[(1, 3), (2, 6), (3, 1)]);
+            assert!(!SignAggr::equals_combination(&shifted, &parts).unwrap());
+            // wrong gamma
+            let bad_gamma = part(13, &[(1, 2), (2, 7), (3, 1)]);
+            assert!(!SignAggr::equals_combination(&bad_gamma, &parts).unwrap());
+            // a part missing
+            assert!(!SignAggr::equals_combination(&total, &parts[..1]).unwrap());
+
+            assert!(SignAggr::equals_combination(&total, &[]).is_err());
+        }
+    }
+
+    mod sign_aggr_bytes_tests {
+
+        use super::*;
+
+        fn aggr() -> SignAggr<8> {
+            SignAggr::new(
+                g1_gen() * Scalar::from(11),
+                vec![Id([1u8; 8]), Id([2u8; 8])],
+                vec![Scalar::from(3), Scalar::from(4)],
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn round_trip() {
+            let a = aggr();
+            let bytes = a.to_bytes();
+            assert_eq!(bytes.len(), G1_COMPRESSED_LEN + 4 + 2 * (8 + SCALAR_LEN));
+            assert_eq!(SignAggr::<8>::from_bytes(&bytes).unwrap(), a);
+
+            let empty = SignAggr::<8>::new(G1::zero(), vec![], vec![]).unwrap();
+            assert_eq!(SignAggr::<8>::from_bytes(&empty.to_bytes()).unwrap(), empty);
+        }
+
+        #[test]
+        fn serialized_len_matches_to_bytes() {
+            let empty = SignAggr::<8>::new(G1::zero(), vec![], vec![]).unwrap();
+            assert_eq!(empty.serialized_len(), empty.to_bytes().len());
+            let a = aggr();
+            assert_eq!(a.serialized_len(), a.to_bytes().len());
+
+            let wide = SignAggr::<32>::new(
+                g1_gen(),
+                (0..5u8).map(|i| Id([i; 32])).collect(),
+                (0..5u64).map(Scalar::from).collect(),
+            )
+            .unwrap();
+            assert_eq!(wide.serialized_len(), wide.to_bytes().len());
+        }
+
+        #[test]
+        fn count_mismatch_is_rejected() {
+            let mut bytes = aggr().to_bytes();
+            bytes[G1_COMPRESSED_LEN..G1_COMPRESSED_LEN + 4]
+                .copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(SignAggr::<8>::from_bytes(&bytes).is_err());
+
+            let bytes = aggr().to_bytes();
+            assert!(SignAggr::<8>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+            assert!(SignAggr::<8>::from_bytes(&bytes[..10]).is_err());
+        }
+
+        #[test]
+        fn duplicate_id_is_rejected() {
+            let a = SignAggr::<8>::new(
+                g1_gen(),
+                vec![Id([1u8; 8]), Id([1u8; 8])],
+                vec![Scalar::from(3), Scalar::from(4)],
+            )
+            .unwrap();
+            assert!(SignAggr::<8>::from_bytes(&a.to_bytes()).is_err());
+        }
+    }
+
+    mod text_encoding_tests {
+
+        use super::*;
+
+        #[test]
+        fn public_key_round_trips() {
+            let pp = Params::<8>::new();
+            let (_, pk) = keygen(&pp, &mut test_rng()).unwrap();
+
+            let from_hex = PublicKey::<8>::from_hex(&pk.to_hex()).unwrap();
+            assert_eq!(from_hex.to_bytes(), pk.to_bytes());
+            let from_b64 = PublicKey::<8>::from_base64(&pk.to_base64()).unwrap();
+            assert_eq!(from_b64.to_bytes(), pk.to_bytes());
+        }
+
+        #[test]
+        fn sign_share_round_trips() {
+            let sh = share::<8>();
+
+            assert_eq!(SignShare::<8>::from_hex(&sh.to_hex()).unwrap(), sh);
+            assert_eq!(SignShare::<8>::from_base64(&sh.to_base64()).unwrap(), sh);
+        }
+
+        #[test]
+        fn sign_aggr_round_trips() {
+            let a =
+                SignAggr::<8>::new(g1_gen(), vec![Id([5u8; 8])], vec![Scalar::from(9)]).unwrap();
+
+            assert_eq!(SignAggr::<8>::from_hex(&a.to_hex()).unwrap(), a);
+            assert_eq!(SignAggr::<8>::from_base64(&a.to_base64()).unwrap(), a);
+        }
+
+        #[test]
+        fn malformed_text_is_invalid_input() {
+            for bad in ["zz", "abc", "not hex at all"] {
+                assert!(matches!(
+                    SignShare::<8>::from_hex(bad),
+                    Err(ProtocolError::InvalidInput(_))
+                ));
+            }
+            for bad in ["***", "abc", "a==="] {
+                assert!(matches!(
+                    SignShare::<8>::from_base64(bad),
+                    Err(ProtocolError::InvalidInput(_))
+                ));
+            }
+        }
+    }
+
+    mod key_eq_tests {
+
+        use super::*;
+
+        #[test]
+        fn keypair_equals_clone_only() {
+            let pp = Params::<8>::new();
+            let mut rng = test_rng();
+            let (sk, pk) = keygen(&pp, &mut rng).unwrap();
+            let (sk2, pk2) = keygen(&pp, &mut rng).unwrap();
+
+            assert_eq!(sk, sk.clone());
+            assert_eq!(pk, pk.clone());
+            assert_ne!(sk, sk2);
+            assert_ne!(pk, pk2);
+
+            // same id, different secret
+            let (rotated, rotated_pk) = sk.rotate(&mut rng);
+            assert_ne!(sk, rotated);
+            assert_ne!(pk, rotated_pk);
+            // same secret, different id
+            assert_ne!(sk, SecretKey::new(sk2.id(), *sk.value()));
+        }
+    }
+
+    mod secret_key_bytes_tests {
+
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let pp = Params::<8>::new();
+            let (sk, _) = keygen(&pp, &mut test_rng()).unwrap();
+
+            let bytes = sk.to_scalar_bytes();
+            let back = SecretKey::from_scalar_bytes(sk.id(), &bytes).unwrap();
+            assert_eq!(back.id(), sk.id());
+            assert_eq!(back.value(), sk.value());
+        }
+
+        #[test]
+        fn zero_scalar_is_rejected() {
+            assert!(matches!(
+                SecretKey::<8>::from_scalar_bytes(Id([1u8; 8]), &[0u8; SCALAR_LEN]),
+                Err(ProtocolError::InvalidInput(_))
+            ));
+        }
+
+        #[test]
+        fn non_canonical_scalar_is_rejected() {
+            assert!(SecretKey::<8>::from_scalar_bytes(Id([1u8; 8]), &[0xff; SCALAR_LEN]).is_err());
+        }
+    }
+
+    mod same_message_tests {
+
+        use super::*;
+
+        #[test]
+        fn equal_messages_detected_despite_gamma() {
+            let pp = Params::<8>::new();
+            let mut rng = test_rng();
+            let (sk, _) = keygen(&pp, &mut rng).unwrap();
+            let m = Scalar::rand(&mut rng);
+
+            let a = sign(&pp, &sk, Label::new(sk.id(), Tag([1u8; 8])), m).unwrap();
+            let b = sign(&pp, &sk, Label::new(sk.id(), Tag([2u8; 8])), m).unwrap();
+            let c = sign(
+                &pp,
+                &sk,
+                Label::new(sk.id(), Tag([3u8; 8])),
+                m + Scalar::from(1),
+            )
+            .unwrap();
+
+            assert_ne!(a.gamma(), b.gamma());
+            assert!(a.is_same_message(&b));
+            assert!(!a.is_same_message(&c));
+
+            let kept = SignShare::dedup_by_message(vec![a.clone(), c.clone(), b, c.clone()]);
+            assert_eq!(kept, vec![a, c]);
+        }
+    }
+
+    /// Random and adversarially framed byte strings through every decoder:
+    /// each must return `Ok` or `Err`, never panic or over-allocate.
+    mod from_bytes_fuzz_tests {
+
+        use super::*;
+
+        use crate::bundle::VerificationBundle;
+        use ark_std::rand::Rng;
+
+        const K: usize = 8;
+
+        fn decode_all(bytes: &[u8]) {
+            let _ = PublicKey::<K>::from_bytes(bytes);
+            let _ = SignShare::<K>::from_bytes(bytes);
+            let _ = SignAggr::<K>::from_bytes(bytes);
+            let _ = VerificationBundle::<K>::from_bytes(bytes);
+            let _ = PreparedProgram::<K>::from_bytes(bytes);
+            let _ = DynAggr::from_bytes(bytes);
+            if let Ok(scalar) = bytes.try_into() {
+                let _ = SecretKey::<K>::from_scalar_bytes(Id([0u8; K]), scalar);
+            }
+        }
+
+        #[test]
+        fn random_bytes_never_panic() {
+            let mut rng = test_rng();
+            for _ in 0..500 {
+                let len = rng.gen_range(0..400);
+                let bytes: Vec<u8> = (0..len).map(|_| rng.r#gen()).collect();
+                decode_all(&bytes);
+            }
+        }
+
+        #[test]
+        fn exact_length_random_bytes_never_panic() {
+            let mut rng = test_rng();
+            let lens = [
+                SCALAR_LEN,
+                K + G2_COMPRESSED_LEN,
+                SignShare::<K>::SERIALIZED_LEN,
+                G1_COMPRESSED_LEN + 4 + 3 * (K + SCALAR_LEN),
+                32 + 4 + 2 * (K + G2_COMPRESSED_LEN),
+                32 + 4 + 2 * (SCALAR_LEN + 2 * K + G1_COMPRESSED_LEN),
+            ];
+            for len in lens {
+                for _ in 0..100 {
+                    let bytes: Vec<u8> = (0..len).map(|_| rng.r#gen()).collect();
+                    decode_all(&bytes);
+                }
+            }
+        }
+
+        #[test]
+        fn mutated_valid_encodings_never_panic() {
+            let mut rng = test_rng();
+            let aggr = SignAggr::<K>::new(
+                g1_gen(),
+                vec![Id([1u8; K]), Id([2u8; K])],
+                vec![Scalar::from(3), Scalar::from(4)],
+            )
+            .unwrap();
+            let seeds = [share::<K>().to_bytes(), aggr.to_bytes()];
+            for seed in seeds {
+                for _ in 0..200 {
+                    let mut bytes = seed.clone();
+                    let i = rng.gen_range(0..bytes.len());
+                    bytes[i] ^= 1 << rng.gen_range(0..8);
+                    if rng.r#gen() {
+                        bytes.truncate(rng.gen_range(0..bytes.len()));
+                    }
+                    decode_all(&bytes);
+                }
+            }
+        }
+
+        /// A prepared program of `n` terms is exactly
+        /// `32 + 4 + n * (SCALAR_LEN + 2K + G1_COMPRESSED_LEN)` bytes; one byte
+        /// fewer or more must be rejected by the length-prefix check.
+        #[test]
+        fn prepared_program_length_is_exact() {
+            let pp = crate::params::Params::<K>::new();
+            let labels = vec![
+                Label::new(Id([1u8; K]), Tag([1u8; K])),
+                Label::new(Id([2u8; K]), Tag([2u8; K])),
+            ];
+            let program = LabeledProgram::new(vec![Scalar::from(3), Scalar::from(4)], labels)
+                .unwrap()
+                .prepare(&pp)
+                .unwrap();
+            let seed = program.to_bytes();
+            assert_eq!(
+                seed.len(),
+                32 + 4 + 2 * (SCALAR_LEN + 2 * K + G1_COMPRESSED_LEN)
+            );
+            let decoded = PreparedProgram::<K>::from_bytes(&seed).unwrap();
+            assert_eq!(decoded.to_bytes(), seed);
+
+            assert!(PreparedProgram::<K>::from_bytes(&seed[..seed.len() - 1]).is_err());
+            let mut long = seed.clone();
+            long.push(0);
+            assert!(PreparedProgram::<K>::from_bytes(&long).is_err());
+
+            let mut rng = test_rng();
+            for _ in 0..100 {
+                let mut bytes = seed.clone();
+                let i = rng.gen_range(0..bytes.len());
+                bytes[i] ^= 1 << rng.gen_range(0..8);
+                match rng.gen_range(0..3) {
+                    0 => bytes.truncate(rng.gen_range(0..bytes.len())),
+                    1 => bytes.extend((0..rng.gen_range(1..64)).map(|_| rng.r#gen::<u8>())),
+                    _ => {}
+                }
+                decode_all(&bytes);
+            }
+        }
+
+        /// The leading byte of a [`DynAggr`] selects `K`: 16 and 32 dispatch
+        /// to the matching decoder, anything else is rejected.
+        #[test]
+        fn dyn_aggr_dispatches_on_k() {
+            let k16 = DynAggr::from(
+                SignAggr::<16>::new(g1_gen(), vec![Id([1u8; 16])], vec![Scalar::from(3)]).unwrap(),
+            );
+            let k32 = DynAggr::from(
+                SignAggr::<32>::new(g1_gen(), vec![Id([1u8; 32])], vec![Scalar::from(3)]).unwrap(),
+            );
+            let seeds = [k16.to_bytes(), k32.to_bytes()];
+            assert_eq!(DynAggr::from_bytes(&seeds[0]).unwrap(), k16);
+            assert_eq!(DynAggr::from_bytes(&seeds[1]).unwrap(), k32);
+
+            // a valid body behind an unsupported K, and a K=16 body tagged 32
+            for k in [0u8, 8, 17, 255] {
+                let mut bytes = seeds[0].clone();
+                bytes[0] = k;
+                assert!(DynAggr::from_bytes(&bytes).is_err());
+            }
+            let mut swapped = seeds[0].clone();
+            swapped[0] = 32;
+            assert!(DynAggr::from_bytes(&swapped).is_err());
+            assert!(DynAggr::from_bytes(&[]).is_err());
+
+            let mut rng = test_rng();
+            for seed in seeds {
+                for _ in 0..100 {
+                    let mut bytes = seed.clone();
+                    let i = rng.gen_range(1..bytes.len());
+                    bytes[i] ^= 1 << rng.gen_range(0..8);
+                    if rng.r#gen() {
+                        bytes.truncate(rng.gen_range(0..bytes.len()));
+                    }
+                    decode_all(&bytes);
+                }
+            }
+        }
+
+        /// Regression: a length prefix claiming `u32::MAX` entries must be
+        /// rejected against the actual input length before any allocation.
+        #[test]
+        fn huge_count_prefix_is_rejected_cheaply() {
+            let mut aggr = vec![0u8; G1_COMPRESSED_LEN + 4];
+            aggr[G1_COMPRESSED_LEN..].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(SignAggr::<K>::from_bytes(&aggr).is_err());
+
+            let mut bundle = vec![0u8; 32 + 4];
+            bundle[32..].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(VerificationBundle::<K>::from_bytes(&bundle).is_err());
+        }
+    }
 }
@@ -0,0 +1,203 @@
+//! Verifier-side state for checking an aggregate while the program streams
+//! in term by term.
+
+use std::collections::HashMap;
+
+use ark_ff::Zero;
+
+use crate::{
+    algebra::{Msm, Scalar, g1_gen, g2_gen, gt_ct_eq, pairing},
+    errors::ProtocolError,
+    params::Params,
+    protocol::signer_pairing_product,
+    types::{Id, Label, PublicKey, SignAggr},
+};
+
+/// Accumulates `(coeff, label, pk)` terms of a program without holding the
+/// program itself: each term is hashed on arrival and folded into its
+/// signer's `sum f_i * H(ell_i)`. Structural problems fail at the offending
+/// [`Verifier::push`]; the pairing check runs once in [`Verifier::finish`],
+/// which accepts exactly what [`verify`](crate::api::verify) accepts over
+/// the same terms.
+pub struct Verifier<'a, const K: usize> {
+    pp: &'a Params<K>,
+    pks: HashMap<Id<K>, PublicKey<K>>,
+    acc: HashMap<Id<K>, Msm>,
+    n: usize,
+}
+
+impl<'a, const K: usize> Verifier<'a, K> {
+    pub fn new(pp: &'a Params<K>) -> Self {
+        Self {
+            pp,
+            pks: HashMap::new(),
+            acc: HashMap::new(),
+            n: 0,
+        }
+    }
+
+    /// Number of terms pushed so far.
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Adds the term `coeff * label`, signed by `pk`. Errors, leaving the
+    /// state unchanged, if `pk` does not carry the label's id, is not in the
+    /// prime-order subgroup, conflicts with a key already pushed for that
+    /// id, or the term would exceed the program size cap.
+    pub fn push(
+        &mut self,
+        coeff: Scalar,
+        label: Label<K>,
+        pk: &PublicKey<K>,
+    ) -> Result<(), ProtocolError> {
+        if pk.id() != label.id() {
+            return Err(ProtocolError::InvalidInput(
+                "public key id does not match label id".to_string(),
+            ));
+        }
+        let value = pk.value_affine();
+        if !value.is_on_curve() || !value.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(ProtocolError::InvalidInput(
+                "public key not in the prime-order subgroup".to_string(),
+            ));
+        }
+        if self.pks.get(&pk.id()).is_some_and(|known| known != pk) {
+            return Err(ProtocolError::InvalidInput(
+                "conflicting public key for id".to_string(),
+            ));
+        }
+        self.pp.check_program_size(self.n + 1)?;
+
+        // zero terms need no hash, matching `verify`
+        if !coeff.is_zero() {
+            let h = self.pp.hash_label(&label)?;
+            self.acc.entry(label.id()).or_default().add(coeff, h);
+        }
+        self.pks.entry(pk.id()).or_insert_with(|| pk.clone());
+        self.n += 1;
+        Ok(())
+    }
+
+    /// Checks `sign_aggr` against the pushed terms and claimed output `msg`.
+    /// Errors if the terms of a signer not in the aggregate do not cancel
+    /// out, or a signer of the aggregate never had its key pushed.
+    pub fn finish(mut self, msg: Scalar, sign_aggr: &SignAggr<K>) -> Result<bool, ProtocolError> {
+        // ver1
+        if sign_aggr.output() != msg {
+            return Ok(false);
+        }
+
+        let ord_ids = sign_aggr.ord_ids();
+        let a: Vec<_> = ord_ids
+            .iter()
+            .zip(sign_aggr.mus())
+            .map(|(id, mu)| {
+                let mut a_j = self.acc.remove(id).unwrap_or_default();
+                a_j.add(*mu, g1_gen());
+                a_j.finalize()
+            })
+            .collect();
+        // signers absent from the aggregate must cancel out, as in `verify`
+        if self.acc.into_values().any(|a_j| !a_j.finalize().is_zero()) {
+            return Err(ProtocolError::InvalidInput(
+                "program label id not in signature ord_ids".to_string(),
+            ));
+        }
+
+        // ver2
+        let rhs = signer_pairing_product(&self.pks, ord_ids, &a)?;
+        let lhs = pairing(sign_aggr.gamma(), &g2_gen());
+        Ok(gt_ct_eq(&lhs, &rhs).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        protocol::{eval, keygen, sign, verify},
+        types::{LabeledProgram, Tag},
+    };
+    use ark_std::{UniformRand, test_rng};
+
+    const K: usize = 8;
+
+    #[test]
+    fn streamed_terms_match_verify() {
+        let pp = Params::<K>::new();
+        let mut rng = test_rng();
+        let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+        let (sk_b, pk_b) = keygen(&pp, &mut rng).unwrap();
+
+        let mut labels = Vec::new();
+        let mut shares = Vec::new();
+        let mut y = Scalar::zero();
+        let coeffs: Vec<Scalar> = (0..4).map(|_| Scalar::rand(&mut rng)).collect();
+        for (i, (sk, f)) in [&sk_a, &sk_b, &sk_a, &sk_b]
+            .into_iter()
+            .zip(&coeffs)
+            .enumerate()
+        {
+            let lab = Label::new(sk.id(), Tag([i as u8; K]));
+            let m = Scalar::rand(&mut rng);
+            shares.push(sign(&pp, sk, lab, m).unwrap());
+            labels.push(lab);
+            y += *f * m;
+        }
+        let program = LabeledProgram::new(coeffs.clone(), labels.clone()).unwrap();
+        let aggr = eval(&pp, &program, shares).unwrap();
+        let pks = HashMap::from([(pk_a.id(), pk_a.clone()), (pk_b.id(), pk_b.clone())]);
+        assert!(verify(&pp, &program, &pks, y, &aggr).unwrap());
+
+        let streamed = |msg: Scalar| {
+            let mut v = Verifier::new(&pp);
+            for (f, lab) in coeffs.iter().zip(&labels) {
+                v.push(*f, *lab, &pks[&lab.id()]).unwrap();
+            }
+            assert_eq!(v.len(), 4);
+            v.finish(msg, &aggr).unwrap()
+        };
+        assert!(streamed(y));
+        assert!(!streamed(y + Scalar::from(1)));
+    }
+
+    #[test]
+    fn mismatched_key_fails_at_push() {
+        let pp = Params::<K>::new();
+        let mut rng = test_rng();
+        let (sk_a, pk_a) = keygen(&pp, &mut rng).unwrap();
+        let (_, pk_b) = keygen(&pp, &mut rng).unwrap();
+        let lab = |i: u8| Label::new(sk_a.id(), Tag([i; K]));
+
+        let mut v = Verifier::new(&pp);
+        v.push(Scalar::from(1), lab(0), &pk_a).unwrap();
+        v.push(Scalar::from(2), lab(1), &pk_a).unwrap();
+
+        assert!(matches!(
+            v.push(Scalar::from(3), lab(2), &pk_b),
+            Err(ProtocolError::InvalidInput(msg)) if msg == "public key id does not match label id"
+        ));
+        // same id, different value
+        let forged = PublicKey::new(sk_a.id(), *pk_b.value());
+        assert!(v.push(Scalar::from(3), lab(2), &forged).is_err());
+        assert_eq!(v.len(), 2);
+
+        // the rejected pushes left the valid terms intact
+        let m = [Scalar::from(5), Scalar::from(7)];
+        let shares = vec![
+            sign(&pp, &sk_a, lab(0), m[0]).unwrap(),
+            sign(&pp, &sk_a, lab(1), m[1]).unwrap(),
+        ];
+        let program =
+            LabeledProgram::new(vec![Scalar::from(1), Scalar::from(2)], vec![lab(0), lab(1)])
+                .unwrap();
+        let aggr = eval(&pp, &program, shares).unwrap();
+        assert!(v.finish(Scalar::from(19), &aggr).unwrap());
+    }
+}